@@ -0,0 +1,396 @@
+//Date Created: 10/18/2026
+//Purpose To record the generation of mazes and caves as animated gif images
+//Notes:
+//  Frames are taken from the observer each generator calls as it works, so the animation shows the real order of carving.
+//  Every frame is drawn from a palette of three colors, floor, wall, and squares of a maze not yet reached.
+
+use std::io;
+use std::io::Write;
+use crate::maze::{Maze, MazeError, Compass, GenerationType};
+use crate::cave::{Cave, CaveError, CaveMethod};
+
+const FLOOR_INDEX: u8 = 0;
+const WALL_INDEX: u8 = 1;
+const UNVISITED_INDEX: u8 = 2;
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum AnimationError {
+    Syntax(String),
+    Io(io::Error),
+    Maze(MazeError),
+    Cave(CaveError),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AnimationError::Syntax(ref err_string) => write!(f,"{}",err_string),
+            AnimationError::Io(ref err) => err.fmt(f),
+            AnimationError::Maze(ref err) => err.fmt(f),
+            AnimationError::Cave(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for AnimationError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            AnimationError::Syntax(ref _err_string) => None,
+            AnimationError::Io(ref err) => Some(err),
+            AnimationError::Maze(ref err) => Some(err),
+            AnimationError::Cave(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for AnimationError {
+    fn from(err: io::Error) -> AnimationError {
+        AnimationError::Io(err)
+    }
+}
+
+impl From<MazeError> for AnimationError {
+    fn from(err: MazeError) -> AnimationError {
+        AnimationError::Maze(err)
+    }
+}
+
+impl From<CaveError> for AnimationError {
+    fn from(err: CaveError) -> AnimationError {
+        AnimationError::Cave(err)
+    }
+}
+
+////////////////////
+//Animation code
+////////////////////
+
+//AnimationSettings
+//Purpose:
+//    To describe how the generation of a level is drawn as an animation.
+//Notes:
+//  The block size is the width in pixels of each square of the wall grid of a maze, or each cell of a cave.
+//  A frame is drawn every frame_skip steps of the generator, and must be positive.
+//  Delays are in hundredths of a second, the final delay is how long the finished level is shown before the animation loops.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct AnimationSettings {
+    pub block_size: usize,
+    pub frame_skip: usize,
+    pub frame_delay: u16,
+    pub final_delay: u16,
+    pub floor_color: image::Rgb<u8>,
+    pub wall_color: image::Rgb<u8>,
+    pub unvisited_color: image::Rgb<u8>,
+}
+
+impl AnimationSettings {
+
+    pub fn init(block_size: usize) -> AnimationSettings {
+        AnimationSettings{block_size, frame_skip: 1, frame_delay: 5, final_delay: 300,
+            floor_color: image::Rgb([255, 255, 255]), wall_color: image::Rgb([0, 0, 0]), unvisited_color: image::Rgb([128, 128, 128])}
+    }
+
+}
+
+//record_maze
+//Purpose:
+//    Creates a rectangular maze as Maze::init_rect does, and writes its generation to the writer as an animated gif.
+//Notes:
+//  The maze is drawn in the same orientation as the text output, with the last row at the top.
+//  The animation starts with no passages carved and always ends on the finished maze.
+pub fn record_maze<W: Write>(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType, settings: &AnimationSettings, writer: W) -> Result<Maze,AnimationError> {
+    let mut recorder = GifRecorder::init(2*my_rows + 1, 2*my_columns + 1, settings, writer)?;
+    recorder.add_frame(&get_maze_frame(&vec![vec![Compass::init(); my_columns]; my_rows], wrap));
+    let my_maze = Maze::init_rect_with_observer(my_rows, my_columns, wrap, method, &mut |maze_matrix| {
+        if recorder.step() {
+            recorder.add_frame(&get_maze_frame(maze_matrix, wrap));
+        }
+    })?;
+    recorder.finish(&get_maze_frame(&my_maze.maze_matrix, wrap))?;
+    Ok(my_maze)
+}
+
+//record_cave
+//Purpose:
+//    Creates a rectangular cave as Cave::init_cave_with_method does, and writes its generation to the writer as an animated gif.
+//Notes:
+//  Only the cellular automaton, and noise smoothed by it, show more than the finished cave.
+//  Cave row 0 is at the top of the animation.
+pub fn record_cave<W: Write>(my_rows: usize, my_columns: usize, wrap: usize, condition_grid: Vec<Vec<usize>>, method: CaveMethod, settings: &AnimationSettings, writer: W) -> Result<Cave,AnimationError> {
+    let mut recorder = GifRecorder::init(my_rows, my_columns, settings, writer)?;
+    let my_cave = Cave::init_cave_with_observer(my_rows, my_columns, wrap, condition_grid, method, &mut |cave_matrix| {
+        if recorder.step() {
+            recorder.add_frame(&get_cave_frame(cave_matrix));
+        }
+    })?;
+    recorder.finish(&get_cave_frame(&my_cave.get_wall_matrix()))?;
+    Ok(my_cave)
+}
+
+//GifRecorder
+//Purpose:
+//    To write frames of palette indices to a gif, one frame every frame_skip steps.
+//Notes:
+//  The observers can't return errors, so the first error writing a frame is kept and returned by finish.
+//  Identical frames in a row are merged by lengthening the delay of the first.
+//  Frames are kept as grids of palette indices and only scaled up to pixels when written.
+struct GifRecorder<W: Write> {
+    encoder: gif::Encoder<W>,
+    settings: AnimationSettings,
+    rows: usize,
+    columns: usize,
+    steps: usize,
+    pending: Option<(Vec<Vec<u8>>,u16)>,
+    written: Option<Vec<Vec<u8>>>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> GifRecorder<W> {
+
+    fn init(grid_rows: usize, grid_columns: usize, settings: &AnimationSettings, writer: W) -> Result<GifRecorder<W>,AnimationError> {
+        if settings.block_size == 0 {
+			return Err(AnimationError::Syntax("The block size must be positive.".to_string()));
+        }
+        if settings.frame_skip == 0 {
+			return Err(AnimationError::Syntax("The frame skip must be positive.".to_string()));
+        }
+        if (grid_rows*settings.block_size > u16::MAX as usize) || (grid_columns*settings.block_size > u16::MAX as usize) {
+			return Err(AnimationError::Syntax(format!("A gif can be at most {} pixels on a side.", u16::MAX)));
+        }
+        let mut palette = Vec::new();
+        for color in [settings.floor_color, settings.wall_color, settings.unvisited_color].iter() {
+            palette.extend_from_slice(&color.0);
+        }
+        let mut encoder = gif::Encoder::new(writer, (grid_columns*settings.block_size) as u16, (grid_rows*settings.block_size) as u16, &palette)?;
+        encoder.write_extension(gif::ExtensionData::Repetitions(gif::Repeat::Infinite))?;
+        Ok(GifRecorder{encoder, settings: *settings, rows: grid_rows, columns: grid_columns, steps: 0, pending: None, written: None, error: None})
+    }
+
+    //step
+    //Purpose:
+    //    Counts a step of the generator and returns true if a frame should be drawn for it.
+    //Notes:
+    //  The first step is always drawn, so a cave's starting cells are shown whatever the frame skip.
+    fn step(&mut self) -> bool {
+        let is_drawn = self.steps % self.settings.frame_skip == 0;
+        self.steps += 1;
+        is_drawn
+    }
+
+    //add_frame
+    //Purpose:
+    //    Adds a frame from a grid of palette indices, indexed by row then column with row 0 at the top.
+    fn add_frame(&mut self, index_grid: &[Vec<u8>]) {
+        if let Some((ref last_grid, ref mut delay)) = self.pending {
+            if last_grid.as_slice() == index_grid {
+                *delay = delay.saturating_add(self.settings.frame_delay);
+                return;
+            }
+        }
+        if let Some(last_frame) = self.pending.take() {
+            self.write_frame(last_frame);
+        }
+        self.pending = Some((index_grid.to_vec(), self.settings.frame_delay));
+    }
+
+    //finish
+    //Purpose:
+    //    Adds the finished level as the last frame, shown for the final delay, and returns the first error met while writing.
+    fn finish(mut self, index_grid: &[Vec<u8>]) -> Result<(),AnimationError> {
+        self.add_frame(index_grid);
+        if let Some((last_grid, delay)) = self.pending.take() {
+            self.write_frame((last_grid, delay.saturating_sub(self.settings.frame_delay).saturating_add(self.settings.final_delay)));
+        }
+        match self.error {
+            Some(err) => Err(AnimationError::Io(err)),
+            None => Ok(()),
+        }
+    }
+
+    //write_frame
+    //Purpose:
+    //    Writes a grid of palette indices as a frame shown for the given delay.
+    //Notes:
+    //  Only the smallest rectangle holding every cell which changed since the last frame written is drawn,
+    //    the rest of the picture is kept from the frames before.
+    fn write_frame(&mut self, (index_grid, delay): (Vec<Vec<u8>>,u16)) {
+        if self.error.is_some() {
+            return;
+        }
+        let (mut top, mut bottom, mut left, mut right) = (0, self.rows, 0, self.columns);
+        if let Some(ref last_grid) = self.written {
+            let changed_rows: Vec<usize> = (0..self.rows).filter(|&i| last_grid[i] != index_grid[i]).collect();
+            let changed_columns: Vec<usize> = (0..self.columns).filter(|&j| (0..self.rows).any(|i| last_grid[i][j] != index_grid[i][j])).collect();
+            //equal frames in a row are merged before they are written, so some cell has always changed.
+            top = changed_rows.first().cloned().unwrap_or(0);
+            bottom = changed_rows.last().map_or(1, |&i| i + 1);
+            left = changed_columns.first().cloned().unwrap_or(0);
+            right = changed_columns.last().map_or(1, |&j| j + 1);
+        }
+        let block_size = self.settings.block_size;
+        let mut pixels = Vec::with_capacity((bottom - top)*(right - left)*block_size*block_size);
+        for index_row in index_grid[top..bottom].iter() {
+            for _y in 0..block_size {
+                for &index in index_row[left..right].iter() {
+                    for _x in 0..block_size {
+                        pixels.push(index);
+                    }
+                }
+            }
+        }
+        let mut frame = gif::Frame::from_indexed_pixels(((right - left)*block_size) as u16, ((bottom - top)*block_size) as u16, &pixels, None);
+        frame.left = (left*block_size) as u16;
+        frame.top = (top*block_size) as u16;
+        frame.dispose = gif::DisposalMethod::Keep;
+        frame.delay = delay;
+        match self.encoder.write_frame(&frame) {
+            Ok(()) => self.written = Some(index_grid),
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+}
+
+//get_maze_frame
+//Purpose:
+//    Returns the palette indices of the wall grid of a maze being generated.
+//Notes:
+//  A square with no passages has not been reached yet, unless the maze is a single square.
+fn get_maze_frame(maze_matrix: &[Vec<Compass>], wrap: usize) -> Vec<Vec<u8>> {
+    let my_rows = maze_matrix.len();
+    let my_columns = maze_matrix[0].len();
+    let my_maze = Maze{rows: my_rows, columns: my_columns, wrap, maze_matrix: maze_matrix.to_vec()};
+    let mut index_grid: Vec<Vec<u8>> = my_maze.get_wall_grid().iter().map(|wall_row| {
+        wall_row.iter().map(|&is_wall| if is_wall { WALL_INDEX } else { FLOOR_INDEX }).collect()
+    }).collect();
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            if my_maze.get_open_neighbors(i, j).is_empty() && (my_rows*my_columns > 1) {
+                let (grid_row, grid_col) = my_maze.get_grid_position(i, j);
+                index_grid[grid_row][grid_col] = UNVISITED_INDEX;
+            }
+        }
+    }
+    index_grid
+}
+
+fn get_cave_frame(cave_matrix: &[Vec<bool>]) -> Vec<Vec<u8>> {
+    cave_matrix.iter().map(|cave_row| {
+        cave_row.iter().map(|&is_wall| if is_wall { WALL_INDEX } else { FLOOR_INDEX }).collect()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the whole picture once a frame is drawn, the frame's rectangle as (left, top, width, height), and its delay.
+    type DecodedFrame = (Vec<u8>,(usize,usize,usize,usize),u16);
+
+    //decode_frames
+    //Purpose:
+    //    Decodes a gif into its frames.
+    fn decode_frames(bytes: &[u8]) -> Vec<DecodedFrame> {
+        let mut reader = gif::Decoder::new(bytes).read_info().unwrap();
+        let width = reader.width() as usize;
+        let mut picture = vec![0; width*(reader.height() as usize)];
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.read_next_frame().unwrap() {
+            let (left, top, frame_width, frame_height) = (frame.left as usize, frame.top as usize, frame.width as usize, frame.height as usize);
+            for y in 0..frame_height {
+                for x in 0..frame_width {
+                    picture[(top + y)*width + left + x] = frame.buffer[y*frame_width + x];
+                }
+            }
+            frames.push((picture.clone(), (left, top, frame_width, frame_height), frame.delay));
+        }
+        frames
+    }
+
+    //get_pixels
+    //Purpose:
+    //    Returns a grid of palette indices scaled up to blocks of pixels, in the order a gif stores them.
+    fn get_pixels(index_grid: &[Vec<u8>], block_size: usize) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        for index_row in index_grid.iter() {
+            for _y in 0..block_size {
+                for &index in index_row.iter() {
+                    pixels.extend(std::iter::repeat(index).take(block_size));
+                }
+            }
+        }
+        pixels
+    }
+
+    //get_changed_rectangle
+    //Purpose:
+    //    Returns the smallest rectangle, as (left, top, width, height), holding every pixel which differs between two pictures.
+    fn get_changed_rectangle(before: &[u8], after: &[u8], width: usize) -> (usize,usize,usize,usize) {
+        let changed: Vec<(usize,usize)> = (0..before.len()).filter(|&i| before[i] != after[i]).map(|i| (i%width, i/width)).collect();
+        let left = changed.iter().map(|&(x,_)| x).min().unwrap();
+        let right = changed.iter().map(|&(x,_)| x).max().unwrap();
+        let top = changed.iter().map(|&(_,y)| y).min().unwrap();
+        let bottom = changed.iter().map(|&(_,y)| y).max().unwrap();
+        (left, top, right - left + 1, bottom - top + 1)
+    }
+
+    #[test]
+    fn maze_animations_skip_frames_and_end_on_the_maze() {
+        //a 4 by 4 maze carves 15 passages. Every passage is drawn with a frame skip of 1, so the finished maze
+        //  is merged into the last of them, while a frame skip of 3 draws passages 1, 4, 7, 10 and 13,
+        //  and the finished maze follows as its own frame.
+        for &(frame_skip, num_frames, last_delay) in [(1, 16, 305), (3, 7, 300)].iter() {
+            let mut settings = AnimationSettings::init(2);
+            settings.frame_skip = frame_skip;
+            let mut bytes = Vec::new();
+            let maze = record_maze(4, 4, 0, GenerationType::Prim, &settings, &mut bytes).unwrap();
+            let frames = decode_frames(&bytes);
+            assert_eq!(frames.len(), num_frames, "frame skip {}", frame_skip);
+            assert_eq!(frames[0].0, get_pixels(&get_maze_frame(&vec![vec![Compass::init(); 4]; 4], 0), 2));
+            assert_eq!(frames[0].1, (0, 0, 18, 18));
+            assert_eq!(frames[num_frames - 1].0, get_pixels(&get_maze_frame(&maze.maze_matrix, 0), 2));
+            assert_eq!(frames[num_frames - 1].2, last_delay);
+            //after the first frame, each frame covers only the part of the picture which changed.
+            for i in 1..num_frames {
+                assert_eq!(frames[i].1, get_changed_rectangle(&frames[i-1].0, &frames[i].0, 18), "frame {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn identical_frames_are_merged() {
+        let settings = AnimationSettings::init(1);
+        let first = vec![vec![WALL_INDEX, FLOOR_INDEX]];
+        let second = vec![vec![WALL_INDEX, WALL_INDEX]];
+        let mut bytes = Vec::new();
+        let mut recorder = GifRecorder::init(1, 2, &settings, &mut bytes).unwrap();
+        recorder.add_frame(&first);
+        recorder.add_frame(&first);
+        recorder.add_frame(&first);
+        recorder.add_frame(&second);
+        recorder.finish(&second).unwrap();
+        let frames = decode_frames(&bytes);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].2, 3*settings.frame_delay);
+        assert_eq!(frames[1].2, settings.frame_delay + settings.final_delay);
+        assert_eq!(frames[1].1, (1, 0, 1, 1));
+    }
+
+    #[test]
+    fn cave_animations_end_on_the_cave() {
+        let mut bytes = Vec::new();
+        let cave = record_cave(10, 12, 0, vec![vec![1; 12]; 10], CaveMethod::CellularAutomaton(0.45, crate::cave::UpdateMode::Synchronous),
+            &AnimationSettings::init(1), &mut bytes).unwrap();
+        let frames = decode_frames(&bytes);
+        //the starting cells and at most one frame for each iteration.
+        assert!(!frames.is_empty() && frames.len() <= 4);
+        assert_eq!(frames[frames.len() - 1].0, get_pixels(&get_cave_frame(&cave.get_wall_matrix()), 1));
+    }
+}
@@ -0,0 +1,506 @@
+//Date Created: 10/18/2026
+//Purpose To lay out mazes as a printable puzzle book in pdf
+//Notes:
+//  The puzzles come first, each with its title, seed and difficulty, and the answer pages follow with the solution drawn over each maze.
+//  The pdf is written directly, with walls drawn as lines and text set in Helvetica, one of the fonts every pdf reader has,
+//    so no fonts are embedded. Only printable ascii is written, other characters in titles become '?'.
+//  Sizes are in points, 72 to the inch, the unit of pdf pages.
+
+use std::fmt::Write;
+use rand::{SeedableRng, XorShiftRng};
+use crate::maze::{Maze, MazeError, GenerationType};
+use crate::noise::hash_lattice;
+use crate::svg::get_wall_segments;
+
+//space left between neighboring mazes on a page, and between a maze and its title.
+const SLOT_PADDING: f64 = 9.0;
+//the width of each digit in Helvetica, as a fraction of the font size.
+const DIGIT_WIDTH: f64 = 0.556;
+//the control point distance of a cubic bezier quarter circle of radius 1.
+const CIRCLE_CONTROL: f64 = 0.5523;
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum BookError {
+    Syntax(String),
+    Maze(MazeError),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BookError::Syntax(ref err_string) => write!(f,"{}",err_string),
+            BookError::Maze(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for BookError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            BookError::Syntax(ref _err_string) => None,
+            BookError::Maze(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<MazeError> for BookError {
+    fn from(err: MazeError) -> BookError {
+        BookError::Maze(err)
+    }
+}
+
+////////////////////
+//Puzzle book code
+////////////////////
+
+//Puzzle
+//Purpose:
+//    A maze of the book, along with the path from its start to its goal.
+//Notes:
+//  The seed is the one the maze was generated from, so that the same maze can be made again.
+//  The difficulty is from 1 to 5, see get_difficulty.
+#[derive(Debug)]
+pub struct Puzzle {
+    pub title: String,
+    pub seed: u64,
+    pub difficulty: usize,
+    pub maze: Maze,
+    pub start: (usize,usize),
+    pub goal: (usize,usize),
+    pub solution: Vec<(usize,usize)>,
+}
+
+impl Puzzle {
+
+    //generate
+    //Purpose:
+    //    Creates a rectangular maze from the seed, to be solved from the bottom left square to the top right square.
+    //Notes:
+    //  The same seed, size and method always give the same maze.
+    pub fn generate(title: &str, my_rows: usize, my_columns: usize, method: GenerationType, seed: u64) -> Result<Puzzle,BookError> {
+        let first_hash = hash_lattice(0, 0, seed);
+        let second_hash = hash_lattice(0, 0, first_hash);
+        let mut rng = XorShiftRng::from_seed([first_hash as u32, (first_hash >> 32) as u32, second_hash as u32, ((second_hash >> 32) as u32) | 1]);
+        let maze = Maze::init_rect_with_rng(my_rows, my_columns, 0, method, &mut rng)?;
+        Puzzle::init(title, seed, maze, (0,0), (my_rows - 1, my_columns - 1))
+    }
+
+    //init
+    //Purpose:
+    //    Makes a puzzle of the maze, solving it and rating its difficulty.
+    //Notes:
+    //  Returns an error if the start or goal is not a square of the maze, or the goal can't be reached from the start.
+    pub fn init(title: &str, seed: u64, maze: Maze, start: (usize,usize), goal: (usize,usize)) -> Result<Puzzle,BookError> {
+        if (start.0 >= maze.rows) || (start.1 >= maze.columns) || (goal.0 >= maze.rows) || (goal.1 >= maze.columns) {
+			return Err(BookError::Syntax("The start and goal must be squares of the maze.".to_string()));
+        }
+        let solution = match maze.get_path(start, goal) {
+            Some(solution) => solution,
+            None => return Err(BookError::Syntax(format!("The goal of {} can't be reached from its start.", title))),
+        };
+        let difficulty = get_difficulty(&maze, &solution);
+        Ok(Puzzle{title: title.to_string(), seed, difficulty, maze, start, goal, solution})
+    }
+
+}
+
+//get_difficulty
+//Purpose:
+//    Rates how hard the solution of a maze is to find, from 1 to 5.
+//Notes:
+//  The rating counts the decisions on the solution, the squares where more than one way on is open.
+//    Each doubling of the decisions past 3 adds one to the rating, so 3 or more is a 2 and 24 or more a 5.
+pub fn get_difficulty(maze: &Maze, solution: &[(usize,usize)]) -> usize {
+    let mut decisions = 0;
+    for (step, &(row, col)) in solution.iter().enumerate().take(solution.len().saturating_sub(1)) {
+        //every square after the start was entered by one of its passages.
+        let ways_on = if step == 0 { maze.get_open_neighbors(row, col).len() } else { maze.get_open_neighbors(row, col).len() - 1 };
+        if ways_on > 1 {
+            decisions += 1;
+        }
+    }
+    1 + [3, 6, 12, 24].iter().filter(|&&threshold| decisions >= threshold).count()
+}
+
+//BookSettings
+//Purpose:
+//    To describe the pages of a puzzle book.
+//Notes:
+//  The default page is US letter with half inch margins.
+//  Puzzles and solutions are laid out in a grid on each page, chosen to make the mazes as large as possible.
+//  The solution is drawn in solution_color, everything else in black.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BookSettings {
+    pub page_width: f64,
+    pub page_height: f64,
+    pub margin: f64,
+    pub puzzles_per_page: usize,
+    pub solutions_per_page: usize,
+    pub solution_color: image::Rgb<u8>,
+}
+
+impl BookSettings {
+
+    pub fn init() -> BookSettings {
+        BookSettings{page_width: 612.0, page_height: 792.0, margin: 36.0, puzzles_per_page: 1, solutions_per_page: 4,
+            solution_color: image::Rgb([200, 0, 0])}
+    }
+
+}
+
+//book_to_pdf
+//Purpose:
+//    Returns a pdf of the puzzles, followed by answer pages with their solutions, with every page numbered.
+pub fn book_to_pdf(puzzles: &[Puzzle], settings: &BookSettings) -> Result<Vec<u8>,BookError> {
+    if puzzles.is_empty() {
+		return Err(BookError::Syntax("A puzzle book needs at least one puzzle.".to_string()));
+    }
+    if (settings.puzzles_per_page == 0) || (settings.solutions_per_page == 0) {
+		return Err(BookError::Syntax("At least one puzzle and one solution must fit on a page.".to_string()));
+    }
+    let inner_sizes = [settings.page_width - 2.0*settings.margin, settings.page_height - 2.0*settings.margin];
+    if settings.margin.is_nan() || (settings.margin < 0.0) || inner_sizes.iter().any(|size| size.is_nan() || *size <= 0.0) {
+		return Err(BookError::Syntax("The margins must leave room on the page.".to_string()));
+    }
+
+    let mut pages = Vec::new();
+    for (per_page, show_solution) in [(settings.puzzles_per_page, false), (settings.solutions_per_page, true)].iter() {
+        let slots = get_slots(*per_page, settings);
+        for page_puzzles in puzzles.chunks(*per_page) {
+            let mut content = String::new();
+            for (puzzle, slot) in page_puzzles.iter().zip(slots.iter()) {
+                draw_puzzle(&mut content, puzzle, *slot, *show_solution, settings);
+            }
+            draw_page_number(&mut content, pages.len() + 1, settings);
+            pages.push(content);
+        }
+    }
+    Ok(write_pdf(&pages, settings))
+}
+
+//get_slots
+//Purpose:
+//    Returns the space on the page for each of the given number of mazes, as (left, bottom, width, height) from the bottom left of the page.
+//Notes:
+//  Of the grids with room for every maze, the one whose cells are closest to square is picked, and the slots fill it row by row from the top.
+fn get_slots(per_page: usize, settings: &BookSettings) -> Vec<(f64,f64,f64,f64)> {
+    let inner_width = settings.page_width - 2.0*settings.margin;
+    let inner_height = settings.page_height - 2.0*settings.margin;
+    let mut best_columns = 1;
+    let mut best_side = 0.0;
+    for grid_columns in 1..(per_page + 1) {
+        let grid_rows = (per_page + grid_columns - 1)/grid_columns;
+        let side = (inner_width/(grid_columns as f64)).min(inner_height/(grid_rows as f64));
+        if side > best_side {
+            best_columns = grid_columns;
+            best_side = side;
+        }
+    }
+    let grid_rows = (per_page + best_columns - 1)/best_columns;
+    let (slot_width, slot_height) = (inner_width/(best_columns as f64), inner_height/(grid_rows as f64));
+    (0..per_page).map(|index| {
+        let (i, j) = (index / best_columns, index % best_columns);
+        (settings.margin + (j as f64)*slot_width, settings.page_height - settings.margin - ((i + 1) as f64)*slot_height, slot_width, slot_height)
+    }).collect()
+}
+
+//draw_puzzle
+//Purpose:
+//    Draws the puzzle with its title and details at the top of the slot and the maze below them, as large as fits.
+//Notes:
+//  The start is marked with a dot and the goal with a square.
+//  A solution step which wraps around the edge of the maze is left undrawn.
+fn draw_puzzle(content: &mut String, puzzle: &Puzzle, slot: (f64,f64,f64,f64), show_solution: bool, settings: &BookSettings) {
+    let (left, bottom, width, height) = (slot.0 + SLOT_PADDING, slot.1 + SLOT_PADDING, slot.2 - 2.0*SLOT_PADDING, slot.3 - 2.0*SLOT_PADDING);
+    let title_size = (height/16.0).clamp(6.0, 16.0);
+    let detail_size = 0.7*title_size;
+    let title = if show_solution { format!("Solution to {}", puzzle.title) } else { puzzle.title.clone() };
+    let top = bottom + height;
+    draw_text(content, &title, "F2", title_size, left, top - title_size);
+    let details = format!("Seed {}    Difficulty {} of 5", puzzle.seed, puzzle.difficulty);
+    draw_text(content, &details, "F1", detail_size, left, top - title_size - 1.4*detail_size);
+
+    //the maze is centered across the slot, just under the details.
+    let maze = &puzzle.maze;
+    let maze_top = top - title_size - 1.4*detail_size - SLOT_PADDING;
+    let cell_size = (width/(maze.columns as f64)).min((maze_top - bottom)/(maze.rows as f64));
+    if cell_size <= 0.0 {
+        return;
+    }
+    let maze_left = left + (width - cell_size*(maze.columns as f64))/2.0;
+    let get_x = |x: f64| maze_left + x*cell_size;
+    let get_y = |y: f64| maze_top - y*cell_size;
+    //the center of the square (row, col), with the last row at the top.
+    let get_center = |(row, col): (usize,usize)| (get_x((col as f64) + 0.5), get_y(((maze.rows - row) as f64) - 0.5));
+
+    writeln!(content, "0 0 0 RG 0 0 0 rg {:.3} w 2 J 0 j", (cell_size/10.0).min(2.0)).unwrap();
+    for ((x0, y0), (x1, y1)) in get_wall_segments(maze) {
+        writeln!(content, "{:.2} {:.2} m {:.2} {:.2} l", get_x(x0 as f64), get_y(y0 as f64), get_x(x1 as f64), get_y(y1 as f64)).unwrap();
+    }
+    content.push_str("S\n");
+
+    if show_solution && puzzle.solution.len() > 1 {
+        let [red, green, blue] = settings.solution_color.0;
+        writeln!(content, "{:.3} {:.3} {:.3} RG {:.3} w 1 J 1 j",
+            (red as f64)/255.0, (green as f64)/255.0, (blue as f64)/255.0, cell_size/4.0).unwrap();
+        for (step, &square) in puzzle.solution.iter().enumerate() {
+            let (x, y) = get_center(square);
+            let is_adjacent = step > 0 && {
+                let last = puzzle.solution[step - 1];
+                (last.0 as i64 - square.0 as i64).abs() + (last.1 as i64 - square.1 as i64).abs() == 1
+            };
+            let operator = if is_adjacent { "l" } else { "m" };
+            writeln!(content, "{:.2} {:.2} {}", x, y, operator).unwrap();
+        }
+        content.push_str("S\n");
+    }
+
+    let (start_x, start_y) = get_center(puzzle.start);
+    draw_circle(content, start_x, start_y, 0.3*cell_size);
+    let (goal_x, goal_y) = get_center(puzzle.goal);
+    writeln!(content, "{:.2} {:.2} {:.2} {:.2} re f", goal_x - 0.25*cell_size, goal_y - 0.25*cell_size, 0.5*cell_size, 0.5*cell_size).unwrap();
+}
+
+//draw_page_number
+//Purpose:
+//    Writes the page number centered in the bottom margin.
+fn draw_page_number(content: &mut String, page_number: usize, settings: &BookSettings) {
+    let font_size = 9.0;
+    let number = page_number.to_string();
+    let x = (settings.page_width - DIGIT_WIDTH*font_size*(number.len() as f64))/2.0;
+    draw_text(content, &number, "F1", font_size, x, (settings.margin - font_size)/2.0);
+}
+
+//draw_text
+//Purpose:
+//    Writes a line of black text in the font with the given resource name, starting at (x, y) on its baseline.
+fn draw_text(content: &mut String, text: &str, font: &str, font_size: f64, x: f64, y: f64) {
+    writeln!(content, "0 0 0 rg BT /{} {:.2} Tf {:.2} {:.2} Td ({}) Tj ET", font, font_size, x, y, get_pdf_string(text)).unwrap();
+}
+
+//draw_circle
+//Purpose:
+//    Fills a black circle, drawn as four bezier curves.
+fn draw_circle(content: &mut String, x: f64, y: f64, radius: f64) {
+    let control = CIRCLE_CONTROL*radius;
+    writeln!(content, "0 0 0 rg {:.2} {:.2} m", x + radius, y).unwrap();
+    writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c", x + radius, y + control, x + control, y + radius, x, y + radius).unwrap();
+    writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c", x - control, y + radius, x - radius, y + control, x - radius, y).unwrap();
+    writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c", x - radius, y - control, x - control, y - radius, x, y - radius).unwrap();
+    writeln!(content, "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c f", x + control, y - radius, x + radius, y - control, x + radius, y).unwrap();
+}
+
+//get_pdf_string
+//Purpose:
+//    Returns the text escaped for a pdf string, with characters outside printable ascii replaced by '?'.
+fn get_pdf_string(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(character);
+            },
+            ' '..='~' => escaped.push(character),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+//write_pdf
+//Purpose:
+//    Returns a pdf file with one page for each content stream.
+//Notes:
+//  Object 1 is the catalog, 2 the page tree and 3 and 4 the regular and bold fonts,
+//    then each page has its content stream followed by the page itself.
+fn write_pdf(pages: &[String], settings: &BookSettings) -> Vec<u8> {
+    let mut objects = Vec::new();
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    let kids: Vec<String> = (0..pages.len()).map(|k| format!("{} 0 R", 6 + 2*k)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {} {}] >>", kids.join(" "), pages.len(), settings.page_width, settings.page_height));
+    for font in ["Helvetica", "Helvetica-Bold"].iter() {
+        objects.push(format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font));
+    }
+    for (k, content) in pages.iter().enumerate() {
+        objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+        objects.push(format!("<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>", 5 + 2*k));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (k, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", k + 1, object).as_bytes());
+    }
+    let xref_offset = pdf.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets.iter() {
+        writeln!(xref, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(xref, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_offset).unwrap();
+    pdf.extend_from_slice(xref.as_bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Compass, Direction};
+
+    //find
+    //Purpose:
+    //    Returns the byte offset of the first match of the needle in the pdf at or after start.
+    fn find(pdf: &[u8], needle: &str, start: usize) -> Option<usize> {
+        pdf[start..].windows(needle.len()).position(|window| window == needle.as_bytes()).map(|position| start + position)
+    }
+
+    //read_line
+    //Purpose:
+    //    Returns the line of the pdf starting at the byte offset, without its newline.
+    fn read_line(pdf: &[u8], start: usize) -> String {
+        let end = find(pdf, "\n", start).unwrap();
+        String::from_utf8(pdf[start..end].to_vec()).unwrap()
+    }
+
+    //get_page_contents
+    //Purpose:
+    //    Returns the content stream of each page of the pdf, in order.
+    fn get_page_contents(pdf: &[u8]) -> Vec<String> {
+        let mut contents = Vec::new();
+        let mut start = 0;
+        while let Some(stream_at) = find(pdf, ">>\nstream\n", start) {
+            let stream_start = stream_at + ">>\nstream\n".len();
+            let stream_end = find(pdf, "\nendstream", stream_start).unwrap();
+            contents.push(String::from_utf8(pdf[stream_start..stream_end].to_vec()).unwrap());
+            start = stream_end;
+        }
+        contents
+    }
+
+    //get_comb_maze
+    //Purpose:
+    //    Returns a maze two rows tall with a corridor along row 0, and a dead end leading north from it at each of the given columns.
+    fn get_comb_maze(my_columns: usize, spurs: &[usize]) -> Maze {
+        let mut maze_matrix = vec![vec![Compass::init(); my_columns]; 2];
+        for j in 0..(my_columns - 1) {
+            maze_matrix[0][j] = maze_matrix[0][j].add_dir(Direction::East);
+            maze_matrix[0][j + 1] = maze_matrix[0][j + 1].add_dir(Direction::West);
+        }
+        for &j in spurs {
+            maze_matrix[0][j] = maze_matrix[0][j].add_dir(Direction::North);
+            maze_matrix[1][j] = maze_matrix[1][j].add_dir(Direction::South);
+        }
+        Maze{rows: 2, columns: my_columns, wrap: 0, maze_matrix}
+    }
+
+    #[test]
+    fn difficulty_doubles_with_the_decisions() {
+        let expected = [(0, 1), (2, 1), (3, 2), (5, 2), (6, 3), (11, 3), (12, 4), (23, 4), (24, 5), (30, 5)];
+        for &(decisions, difficulty) in expected.iter() {
+            //a dead end beside each square between the start and the goal makes each of them a decision.
+            let spurs: Vec<usize> = (1..(decisions + 1)).collect();
+            let maze = get_comb_maze(decisions + 2, &spurs);
+            let puzzle = Puzzle::init("Comb", 0, maze, (0,0), (0,decisions + 1)).unwrap();
+            assert_eq!(puzzle.solution.len(), decisions + 2);
+            assert_eq!(puzzle.difficulty, difficulty, "{} decisions", decisions);
+        }
+        //a dead end beside the goal is never a decision, since the solution stops there.
+        let maze = get_comb_maze(5, &[4]);
+        assert_eq!(get_difficulty(&maze, &[(0,0), (0,1), (0,2), (0,3), (0,4)]), 1);
+    }
+
+    #[test]
+    fn puzzles_are_generated_from_their_seed() {
+        let first = Puzzle::generate("First", 9, 12, GenerationType::Backtrack(0.5), 42).unwrap();
+        let second = Puzzle::generate("Second", 9, 12, GenerationType::Backtrack(0.5), 42).unwrap();
+        let other = Puzzle::generate("Other", 9, 12, GenerationType::Backtrack(0.5), 43).unwrap();
+        assert_eq!(first.maze, second.maze);
+        assert_eq!(first.solution, second.solution);
+        assert_ne!(first.maze, other.maze);
+        assert_eq!((first.start, first.goal), ((0,0), (8,11)));
+    }
+
+    #[test]
+    fn solutions_are_drawn_only_on_answer_pages() {
+        let puzzles: Vec<Puzzle> = (0..7).map(|i| Puzzle::generate(&format!("Maze {}", i + 1), 6, 6, GenerationType::Prim, i).unwrap()).collect();
+        let mut settings = BookSettings::init();
+        settings.puzzles_per_page = 2;
+        settings.solutions_per_page = 3;
+        let pdf = book_to_pdf(&puzzles, &settings).unwrap();
+        let contents = get_page_contents(&pdf);
+        //four pages of puzzles, then three of answers.
+        assert_eq!(contents.len(), 4 + 3);
+        assert!(find(&pdf, "/Count 7 ", 0).is_some());
+        for (k, content) in contents.iter().enumerate() {
+            let is_answer_page = k >= 4;
+            assert_eq!(content.contains(" w 1 J 1 j"), is_answer_page, "page {}", k + 1);
+            assert_eq!(content.contains("(Solution to "), is_answer_page, "page {}", k + 1);
+            assert!(content.contains(&format!("({}) Tj", k + 1)), "page {} is unnumbered", k + 1);
+        }
+        assert_eq!(contents[3].matches("(Maze ").count(), 1);
+        assert_eq!(contents[6].matches("(Solution to ").count(), 1);
+    }
+
+    #[test]
+    fn books_which_cant_be_laid_out_are_rejected() {
+        let puzzles = vec![Puzzle::generate("Maze", 6, 6, GenerationType::Prim, 1).unwrap()];
+        assert!(book_to_pdf(&[], &BookSettings::init()).is_err());
+        let mut settings = BookSettings::init();
+        settings.puzzles_per_page = 0;
+        assert!(book_to_pdf(&puzzles, &settings).is_err());
+        let mut settings = BookSettings::init();
+        settings.solutions_per_page = 0;
+        assert!(book_to_pdf(&puzzles, &settings).is_err());
+        for &margin in [306.0, 400.0, -1.0, f64::NAN].iter() {
+            let mut settings = BookSettings::init();
+            settings.margin = margin;
+            assert!(book_to_pdf(&puzzles, &settings).is_err(), "margin {}", margin);
+        }
+        assert!(book_to_pdf(&puzzles, &BookSettings::init()).is_ok());
+    }
+
+    #[test]
+    fn xref_offsets_point_at_their_objects() {
+        let puzzles: Vec<Puzzle> = (0..6).map(|i| Puzzle::generate(&format!("Maze {}", i + 1), 8, 8, GenerationType::Wilson, 100 + i).unwrap()).collect();
+        let mut settings = BookSettings::init();
+        settings.puzzles_per_page = 2;
+        let pdf = book_to_pdf(&puzzles, &settings).unwrap();
+
+        let startxref = find(&pdf, "startxref\n", 0).unwrap();
+        let xref_offset: usize = read_line(&pdf, startxref + "startxref\n".len()).parse().unwrap();
+        assert_eq!(read_line(&pdf, xref_offset), "xref");
+        let subsection = read_line(&pdf, xref_offset + "xref\n".len());
+        let num_entries: usize = subsection.trim_start_matches("0 ").parse().unwrap();
+        //the free entry for object 0 comes first, then one 20 byte entry for each object.
+        let first_entry = xref_offset + "xref\n".len() + subsection.len() + 1;
+        assert_eq!(read_line(&pdf, first_entry), "0000000000 65535 f ");
+        for k in 1..num_entries {
+            let entry = read_line(&pdf, first_entry + 20*k);
+            assert!(entry.ends_with(" 00000 n "), "entry {} is {:?}", k, entry);
+            let offset: usize = entry[..10].parse().unwrap();
+            assert_eq!(read_line(&pdf, offset), format!("{} 0 obj", k));
+        }
+        assert_eq!(read_line(&pdf, first_entry + 20*num_entries), "trailer");
+
+        //each stream is as long as its length says, and every page has one.
+        let mut start = 0;
+        let mut num_streams = 0;
+        while let Some(length_at) = find(&pdf, "<< /Length ", start) {
+            let length_line = read_line(&pdf, length_at);
+            let length: usize = length_line["<< /Length ".len()..length_line.len() - " >>".len()].parse().unwrap();
+            let stream_start = length_at + length_line.len() + "\nstream\n".len();
+            assert_eq!(&pdf[(stream_start + length)..(stream_start + length + "\nendstream".len())], b"\nendstream");
+            start = stream_start + length;
+            num_streams += 1;
+        }
+        assert_eq!(num_entries, 5 + 2*num_streams);
+    }
+}
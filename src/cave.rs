@@ -3,10 +3,23 @@
 //Purpose To create caves
 //Notes:
 
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
 use rand::Rng;
+use crate::noise::{NoiseSettings, NoiseError, noise_grid, hash_lattice};
+use crate::maze::GenerationEvent;
 
 const NUM_OF_ITERS: usize = 3;
-const DEFULT_WALL_PROB: f64 = 0.45;
+const DRUNKARD_WALK_LENGTH: usize = 100;
+const DIG_STEP_LIMIT: usize = 1000;
+const RELEASE_MARGIN: usize = 3;
+const WATER_ENCLOSURE: f64 = 0.5;
+const WATER_SCALE: f64 = 8.0;
+const LAVA_SCALE: f64 = 12.0;
+const MASK_WALL_LUMA: u8 = 64;
+const MASK_FLOOR_LUMA: u8 = 192;
+pub const DEFULT_WALL_PROB: f64 = 0.45;
 
 ////////////////////
 //Custom Error handling code
@@ -15,36 +28,165 @@ const DEFULT_WALL_PROB: f64 = 0.45;
 #[derive(Debug)]
 pub enum CaveError {
     Syntax(String),
+    Io(io::Error),
+    Image(image::ImageError),
+    Noise(NoiseError),
 }
 
 use std::fmt;
+use std::io;
 use std::error::Error;
 
 impl fmt::Display for CaveError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             CaveError::Syntax(ref err_string) => write!(f,"{}",err_string),
+            CaveError::Io(ref err) => write!(f,"{}",err),
+            CaveError::Image(ref err) => write!(f,"{}",err),
+            CaveError::Noise(ref err) => write!(f,"{}",err),
         }
     }
 }
 
 impl Error for CaveError {
-    fn cause(&self) -> Option<&Error> {
+    fn cause(&self) -> Option<&dyn Error> {
         match *self {
             CaveError::Syntax(ref _err_string) => None,
+            CaveError::Io(ref err) => Some(err),
+            CaveError::Image(ref err) => Some(err),
+            CaveError::Noise(ref err) => Some(err),
         }
     }
 }
 
+impl From<io::Error> for CaveError {
+    fn from(err: io::Error) -> CaveError {
+        CaveError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for CaveError {
+    fn from(err: image::ImageError) -> CaveError {
+        CaveError::Image(err)
+    }
+}
+
+impl From<NoiseError> for CaveError {
+    fn from(err: NoiseError) -> CaveError {
+        CaveError::Noise(err)
+    }
+}
+
 ////////////////////
 //Cave code
 ////////////////////
 
+//UpdateMode
+//Purpose:
+//    To denote how the cellular automaton advances the cave each iteration.
+//Notes:
+//  Synchronous reads the whole previous generation and writes the next one.
+//  Asynchronous updates the cave in place, so cells later in the scan see neighbors that have already been updated.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateMode {
+    Synchronous,
+    Asynchronous,
+}
+
+//CaveMethod
+//Purpose:
+//    To denote which cave generation algorithm to use.
+//Notes:
+//  The cellular automaton takes the probability a cell starts as a wall and how the automaton is updated.
+//  The drunkard's walk and aggregation methods take the fraction of the cave which should end up as floor.
+//  The noise method takes the noise to threshold, and optionally an update mode to smooth the result with the cellular automaton.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaveMethod {
+    CellularAutomaton(f64,UpdateMode),
+    DrunkardsWalk(f64),
+    Aggregation(f64),
+    Noise(NoiseSettings,Option<UpdateMode>),
+}
+
+//Tile
+//Purpose:
+//    To record what material fills a cell of the cave.
+//Notes:
+//  Water and lava lie on the cave floor, while ore is found in the walls,
+//    so a cave counts exactly its wall and ore tiles as walls.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tile {
+    Floor,
+    Wall,
+    Water,
+    Lava,
+    Ore,
+}
+
+impl Tile {
+
+    pub fn is_wall(self) -> bool {
+        match self {
+            Tile::Wall | Tile::Ore => true,
+            Tile::Floor | Tile::Water | Tile::Lava => false,
+        }
+    }
+
+    //glyph
+    //Purpose:
+    //    Returns the character used for the tile in text output.
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Floor => ' ',
+            Tile::Wall => '#',
+            Tile::Water => '~',
+            Tile::Lava => '^',
+            Tile::Ore => '*',
+        }
+    }
+}
+
+//MaterialSettings
+//Purpose:
+//    To describe how water, lava and ore are spread through a cave.
+//Notes:
+//  Water starts wherever a layer of noise, raised by half the fraction of neighboring walls, is above water_threshold,
+//    and is then pooled by the water automaton, so 1.5 means no water.
+//  Lava fills the floor wherever a separate layer of noise is above lava_threshold, so 1.0 means no lava.
+//  Ore_prob is the chance a wall cell bordering the floor is ore.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialSettings {
+    pub water_threshold: f64,
+    pub lava_threshold: f64,
+    pub ore_prob: f64,
+    pub seed: u64,
+}
+
+impl MaterialSettings {
+
+    pub fn init(seed: u64) -> MaterialSettings {
+        MaterialSettings{water_threshold: 0.75, lava_threshold: 0.68, ore_prob: 0.05, seed}
+    }
+
+}
+
+//Cave
+//Purpose:
+//    To hold a generated cave.
+//Notes:
+//  The tiles are the only record of the cave, which cells are walls is always read from them.
+//    The wall grid which caves used to store is still given by the deprecated cave_matrix method.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cave {
     pub rows: usize,
 	pub columns: usize,
-	pub cave_matrix: Vec<Vec<bool>>
+	pub wrap: usize,
+	pub tile_matrix: Vec<Vec<Tile>>
 }
 
 impl Cave {
@@ -52,7 +194,7 @@ impl Cave {
     pub fn init_cave(my_rows: usize, my_columns: usize) -> Result<Cave,CaveError> {
 		let condition_grid = vec![vec![1; my_columns]; my_rows];
 
-		return Cave::init_cave_with_conditions(my_rows,my_columns,DEFULT_WALL_PROB,condition_grid);
+		Cave::init_cave_with_conditions(my_rows,my_columns,0,DEFULT_WALL_PROB,condition_grid,UpdateMode::Synchronous)
     }
 
     //init_cave_with_conditions
@@ -62,34 +204,641 @@ impl Cave {
     //    The variables max_rows and max_cols are non-zero.
     //Notes:
     //  Wrap indicates if the rectangle should be considered as having its sides meet up.
-    //    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
-    pub fn init_cave_with_conditions(my_rows: usize, my_columns: usize, wall_prob: f64, condition_grid: Vec<Vec<usize>>) -> Result<Cave,CaveError> {
+    //    A wrap of 0 means no wrapping, 1 means the east and west sides meet (a tube), and 2 means wrapping on both pairs of sides (a torus).
+    //  The update mode decides if each iteration of the automaton reads only the previous generation or updates in place.
+    pub fn init_cave_with_conditions(my_rows: usize, my_columns: usize, wrap: usize, wall_prob: f64, condition_grid: Vec<Vec<usize>>, update_mode: UpdateMode) -> Result<Cave,CaveError> {
+        Cave::init_cave_with_method(my_rows,my_columns,wrap,condition_grid,CaveMethod::CellularAutomaton(wall_prob,update_mode))
+    }
+
+    //init_cave_with_method
+    //Purpose:
+    //    Creates a rectangular cave using either a cellular automaton, a drunkard's walk, diffusion limited aggregation, or noise.
+    //Pre-Conditions:
+    //    The variables max_rows and max_cols are non-zero.
+    //Notes:
+    //  Wrap indicates if the rectangle should be considered as having its sides meet up.
+    //    A wrap of 0 means no wrapping, 1 means the east and west sides meet (a tube), and 2 means wrapping on both pairs of sides (a torus).
+    //  Every method leaves cells with a condition of 0 as floor and cells with a condition of 2 as wall.
+    pub fn init_cave_with_method(my_rows: usize, my_columns: usize, wrap: usize, condition_grid: Vec<Vec<usize>>, method: CaveMethod) -> Result<Cave,CaveError> {
+        Cave::init_cave_with_observer(my_rows,my_columns,wrap,condition_grid,method,&mut |_| ())
+    }
+
+    //init_cave_with_observer
+    //Purpose:
+    //    Creates a rectangular cave as init_cave_with_method does, calling the observer with the cave matrix as it is made.
+    //Notes:
+    //  The cellular automaton, and noise smoothed by it, call the observer with the starting cells and again after every iteration.
+    //  The other methods only call the observer once with the finished cave.
+    pub fn init_cave_with_observer(my_rows: usize, my_columns: usize, wrap: usize, condition_grid: Vec<Vec<usize>>, method: CaveMethod, observer: &mut dyn FnMut(&[Vec<bool>])) -> Result<Cave,CaveError> {
+        let mut generator = CaveGenerator::init(my_rows,my_columns,wrap,condition_grid,method)?;
+        observer(generator.get_matrix());
+        while generator.next().is_some() {
+            observer(generator.get_matrix());
+        }
+		Ok(generator.into_cave())
+    }
+
+    //from_walls
+    //Purpose:
+    //    Creates a cave of plain walls and floor from a matrix which is true on walls.
+    pub fn from_walls(wall_matrix: &[Vec<bool>], wrap: usize) -> Cave {
+        let rows = wall_matrix.len();
+        let columns = wall_matrix.first().map_or(0, |wall_row| wall_row.len());
+        Cave{rows, columns, wrap, tile_matrix: get_plain_tiles(wall_matrix)}
+    }
+
+    //is_wall
+    //Purpose:
+    //    Returns if the cell at (row, col) is a wall, which it is when its tile is a wall or ore.
+    pub fn is_wall(&self, row: usize, col: usize) -> bool {
+        self.tile_matrix[row][col].is_wall()
+    }
+
+    //get_wall_matrix
+    //Purpose:
+    //    Returns a matrix the size of the cave which is true on walls.
+    pub fn get_wall_matrix(&self) -> Vec<Vec<bool>> {
+        self.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|tile| tile.is_wall()).collect()).collect()
+    }
+
+    //cave_matrix
+    //Purpose:
+    //    Returns the wall grid in place of the old cave_matrix field, true on walls and ore.
+    #[deprecated(note = "a cave is stored as tiles, use get_wall_matrix or is_wall instead")]
+    pub fn cave_matrix(&self) -> Vec<Vec<bool>> {
+        self.get_wall_matrix()
+    }
+
+    //floor_mask
+    //Purpose:
+    //    Returns a matrix the size of the cave which is true on floor cells,
+    //    in the form Maze::init_rect_with_bitmask takes so a maze can be carved into the cave.
+    pub fn floor_mask(&self) -> Vec<Vec<bool>> {
+        self.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|tile| !tile.is_wall()).collect()).collect()
+    }
+
+    //get_distances
+    //Purpose:
+    //    Returns the number of steps from the start to every floor cell reachable from it, with None for the other cells.
+    //Notes:
+    //  Steps are to cells sharing an edge, and wrap around the sides of the cave that wrap.
+    pub fn get_distances(&self, start: (usize,usize)) -> Vec<Vec<Option<usize>>> {
+        let mut distances = vec![vec![None; self.columns]; self.rows];
+        distances[start.0][start.1] = Some(0);
+        let mut frontier = std::collections::VecDeque::new();
+        frontier.push_back(start);
+        while let Some((row,col)) = frontier.pop_front() {
+            let distance = distances[row][col].unwrap();
+            for (next_row,next_col) in get_cave_neighbors(self.rows,self.columns,row,col,self.wrap) {
+                if !self.is_wall(next_row,next_col) && distances[next_row][next_col].is_none() {
+                    distances[next_row][next_col] = Some(distance + 1);
+                    frontier.push_back((next_row,next_col));
+                }
+            }
+        }
+        distances
+    }
+
+    //get_regions
+    //Purpose:
+    //    Returns every connected region of floor in the cave, each as a list of (row, column) cells.
+    //Notes:
+    //  Cells are connected if they share an edge, diagonal neighbors are not connected.
+    //  Cells on opposite sides of the cave are connected if the cave wraps there.
+    //  The regions are ordered from largest to smallest.
+    pub fn get_regions(&self) -> Vec<Vec<(usize,usize)>> {
+        let label_matrix = self.label_regions();
+        let mut regions: Vec<Vec<(usize,usize)>> = Vec::new();
+        for (i, label_row) in label_matrix.iter().enumerate() {
+            for (j, &cell_label) in label_row.iter().enumerate() {
+                if let Some(label) = cell_label {
+                    if label == regions.len() {
+                        regions.push(Vec::new());
+                    }
+                    regions[label].push((i,j));
+                }
+            }
+        }
+        regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+        regions
+    }
+
+    //label_regions
+    //Purpose:
+    //    Returns a matrix the size of the cave where every floor cell is labeled with the region it belongs to.
+    //Notes:
+    //  Walls are labeled with None.
+    //  Labels are given in the order the regions are first found while scanning rows, starting from 0.
+    pub fn label_regions(&self) -> Vec<Vec<Option<usize>>> {
+        let mut label_matrix = vec![vec![None; self.columns]; self.rows];
+        let mut next_label = 0;
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                if !self.is_wall(i,j) && label_matrix[i][j].is_none() { //if the cell is floor and hasn't been flooded.
+                    let mut frontier = vec![(i,j)];
+                    label_matrix[i][j] = Some(next_label);
+                    while let Some((row,col)) = frontier.pop() { //flood adjcent floor cells.
+                        for (next_row,next_col) in get_cave_neighbors(self.rows,self.columns,row,col,self.wrap) {
+                            if !self.is_wall(next_row,next_col) && label_matrix[next_row][next_col].is_none() {
+                                label_matrix[next_row][next_col] = Some(next_label);
+                                frontier.push((next_row,next_col));
+                            }
+                        }
+                    }
+                    next_label += 1;
+                }
+            }
+        }
+        label_matrix
+    }
+
+    //cull_regions
+    //Purpose:
+    //    Fills in every region of floor with fewer than min_size cells.
+    pub fn cull_regions(&mut self, min_size: usize) {
+        for region in self.get_regions() {
+            if region.len() < min_size {
+                for (row,col) in region {
+                    self.tile_matrix[row][col] = Tile::Wall;
+                }
+            }
+        }
+    }
+
+    //join_regions
+    //Purpose:
+    //    Digs tunnels so that every region of floor is connected to the largest one.
+    //Notes:
+    //  Each region is joined by the tunnel that removes the fewest walls, and once joined
+    //    it counts as part of the largest region for the regions that follow.
+    //  A tunnel may pass through other regions, which costs nothing since they are already floor.
+    //  Tunnels never pass through cells with a condition of 2, so a region walled in by them is left unjoined.
+    pub fn join_regions(&mut self, condition_grid: &[Vec<usize>]) {
+        let regions = self.get_regions();
+        if regions.len() <= 1 {
+            return;
+        }
+        let mut connected = vec![vec![false; self.columns]; self.rows];
+        for &(row,col) in regions[0].iter() {
+            connected[row][col] = true;
+        }
+        for region in regions.iter().skip(1) {
+            let tunnel = self.find_tunnel(region, &connected, condition_grid);
+            for (row,col) in tunnel {
+                self.tile_matrix[row][col] = Tile::Floor;
+                connected[row][col] = true;
+            }
+            for &(row,col) in region.iter() {
+                connected[row][col] = true;
+            }
+        }
+    }
+
+    //place_materials
+    //Purpose:
+    //    Spreads water, lava and ore through the cave, replacing any materials placed before.
+    //Notes:
+    //  Water starts mostly in floor cells enclosed by walls and is pooled by its own automaton,
+    //    so it collects in the narrow pockets of the cave.
+    //  Lava is placed after water, so where they overlap the cell is lava.
+    pub fn place_materials(&mut self, settings: &MaterialSettings) {
+        let wall_matrix = self.get_wall_matrix();
+        self.tile_matrix = get_plain_tiles(&wall_matrix);
+
+        //water is most likely where the floor is enclosed by walls, and is clumped together by noise.
+        let water_noise = noise_grid(self.rows, self.columns, self.wrap, &NoiseSettings::init(WATER_SCALE, settings.seed.wrapping_add(1)))
+            .expect("the default noise settings have a valid number of octaves");
+        let mut water_matrix = vec![vec![false; self.columns]; self.rows];
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                if !wall_matrix[i][j] {
+                    let enclosure = neighbor_wall_ratio(self.rows,self.columns,self.wrap,i,j,&wall_matrix);
+                    water_matrix[i][j] = water_noise[i][j] + enclosure/2.0 > settings.water_threshold;
+                }
+            }
+        }
+        for _i in 0..NUM_OF_ITERS {
+            water_matrix = water_auto_iter(self.rows, self.columns, self.wrap, &water_matrix, &wall_matrix);
+        }
+
+        let lava_noise = noise_grid(self.rows, self.columns, self.wrap, &NoiseSettings::init(LAVA_SCALE, settings.seed))
+            .expect("the default noise settings have a valid number of octaves");
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                if wall_matrix[i][j] {
+                    let borders_floor = get_cave_neighbors(self.rows,self.columns,i,j,self.wrap).iter().any(|&(row,col)| !wall_matrix[row][col]);
+                    //ore is drawn from the seed like water and lava, so the same seed gives the same ore.
+                    let ore_value = (hash_lattice(j as i64, i as i64, settings.seed.wrapping_add(2)) >> 11) as f64/((1u64 << 53) as f64);
+                    if borders_floor && ore_value < settings.ore_prob {
+                        self.tile_matrix[i][j] = Tile::Ore;
+                    }
+                } else if lava_noise[i][j] > settings.lava_threshold {
+                    self.tile_matrix[i][j] = Tile::Lava;
+                } else if water_matrix[i][j] {
+                    self.tile_matrix[i][j] = Tile::Water;
+                }
+            }
+        }
+    }
+
+    //find_tunnel
+    //Purpose:
+    //    Returns the cells which must be dug out to connect the region to the connected cells.
+    //Notes:
+    //  Uses a breadth first search where moving through floor is free and moving through a wall costs one,
+    //    so the tunnel returned removes as few walls as possible.
+    //  Cells with a condition of 2 can not be dug, and if no tunnel avoids them the tunnel returned is empty.
+    fn find_tunnel(&self, region: &[(usize,usize)], connected: &[Vec<bool>], condition_grid: &[Vec<usize>]) -> Vec<(usize,usize)> {
+        let mut cost = vec![vec![usize::MAX; self.columns]; self.rows];
+        let mut previous: Vec<Vec<Option<(usize,usize)>>> = vec![vec![None; self.columns]; self.rows];
+        let mut queue = VecDeque::new();
+        for &(row,col) in region.iter() {
+            cost[row][col] = 0;
+            queue.push_back((row,col));
+        }
+        let mut goal = None;
+        while let Some((row,col)) = queue.pop_front() {
+            if connected[row][col] {
+                goal = Some((row,col));
+                break;
+            }
+            for (next_row,next_col) in get_cave_neighbors(self.rows,self.columns,row,col,self.wrap) {
+                if condition_grid[next_row][next_col] == 2 { //forced walls can never be dug.
+                    continue;
+                }
+                let step = if self.is_wall(next_row,next_col) { 1 } else { 0 };
+                if cost[row][col] + step < cost[next_row][next_col] {
+                    cost[next_row][next_col] = cost[row][col] + step;
+                    previous[next_row][next_col] = Some((row,col));
+                    if step == 0 {
+                        queue.push_front((next_row,next_col));
+                    } else {
+                        queue.push_back((next_row,next_col));
+                    }
+                }
+            }
+        }
+        let mut tunnel = Vec::new();
+        let mut current = goal;
+        while let Some((row,col)) = current {
+            if self.is_wall(row,col) {
+                tunnel.push((row,col));
+            }
+            current = previous[row][col];
+        }
+        tunnel
+    }
+
+}
+
+//CaveGenerator
+//Purpose:
+//    Generates a cave one iteration of the cellular automaton at a time, as an iterator over the events of its generation.
+//Notes:
+//  The starting cells are made when the generator is created, and each call to next runs one iteration
+//    and gives a CaveIteration event, so generation can be paused between iterations or stopped early.
+//  Methods other than the cellular automaton and smoothed noise are finished when the generator is created, and give no events.
+pub struct CaveGenerator {
+    rows: usize,
+    columns: usize,
+    wrap: usize,
+    cave_matrix: Vec<Vec<bool>>,
+    condition_grid: Vec<Vec<usize>>,
+    smoothing: Option<UpdateMode>,
+    iteration: usize,
+}
+
+impl CaveGenerator {
+
+    //init
+    //Purpose:
+    //    Starts generating a rectangular cave as Cave::init_cave_with_method does.
+    //Pre-Conditions:
+    //    The variables max_rows and max_cols are non-zero.
+    pub fn init(my_rows: usize, my_columns: usize, wrap: usize, condition_grid: Vec<Vec<usize>>, method: CaveMethod) -> Result<CaveGenerator,CaveError> {
 		if (my_rows == 0) || (my_columns == 0) {
 			return Err(CaveError::Syntax("A cave requires non-zero dimensions.".to_string()));
 		}else{
-			if (my_rows as usize) != condition_grid.len() {
+			if my_rows != condition_grid.len() {
 				return Err(CaveError::Syntax("Given matrix does not match given dimensions.".to_string()));
 			}
 
-			for i in 0..my_rows {
-				if (my_columns as usize) != condition_grid[i as usize].len() {
+			for condition_row in condition_grid.iter() {
+				if my_columns != condition_row.len() {
 					return Err(CaveError::Syntax("Given matrix does not match given dimensions.".to_string()));
 				}
 			}
 		}
 
-        let mut matrix = init_cave_matrix(my_rows, my_columns, wall_prob, &condition_grid);
+        let matrix;
+        let smoothing;
+        match method {
+            CaveMethod::CellularAutomaton(wall_prob,update_mode) => {
+                matrix = init_cave_matrix(my_rows, my_columns, wall_prob, &condition_grid);
+                smoothing = Some(update_mode);
+            },
+            CaveMethod::DrunkardsWalk(floor_ratio) => {
+                matrix = drunkards_walk(my_rows, my_columns, wrap, floor_ratio, &condition_grid);
+                smoothing = None;
+            },
+            CaveMethod::Aggregation(floor_ratio) => {
+                matrix = diffusion_limited_aggregation(my_rows, my_columns, wrap, floor_ratio, &condition_grid);
+                smoothing = None;
+            },
+            CaveMethod::Noise(settings,noise_smoothing) => {
+                if settings.scale.is_nan() || settings.scale <= 0.0 {
+                    return Err(CaveError::Syntax("The scale of the noise must be positive.".to_string()));
+                }
+                matrix = init_noise_matrix(my_rows, my_columns, wrap, &settings, &condition_grid)?;
+                smoothing = noise_smoothing;
+            },
+        }
+
+
+		Ok(CaveGenerator{rows: my_rows, columns: my_columns, wrap, cave_matrix: matrix, condition_grid, smoothing, iteration: 0})
+    }
+
+    //get_matrix
+    //Purpose:
+    //    Returns the cells of the cave so far, true for walls, in the same form as the cave matrix.
+    pub fn get_matrix(&self) -> &[Vec<bool>] {
+        &self.cave_matrix
+    }
+
+    //into_cave
+    //Purpose:
+    //    Finishes generating the cave and returns it.
+    pub fn into_cave(mut self) -> Cave {
+        while self.next().is_some() {}
+        Cave::from_walls(&self.cave_matrix, self.wrap)
+    }
+
+}
+
+impl Iterator for CaveGenerator {
+    type Item = GenerationEvent;
+
+    fn next(&mut self) -> Option<GenerationEvent> {
+        match self.smoothing {
+            Some(update_mode) if self.iteration < NUM_OF_ITERS => {
+                cell_auto_iter(self.rows, self.columns, self.wrap, &mut self.cave_matrix, &self.condition_grid, update_mode);
+                self.iteration += 1;
+                Some(GenerationEvent::CaveIteration(self.iteration))
+            },
+            _ => None,
+        }
+    }
+}
+
+//get_plain_tiles
+//Purpose:
+//    Returns the tiles of a cave with only floor and walls.
+fn get_plain_tiles(cave_matrix: &[Vec<bool>]) -> Vec<Vec<Tile>> {
+    cave_matrix.iter().map(|cave_row| {
+        cave_row.iter().map(|&is_wall| if is_wall { Tile::Wall } else { Tile::Floor }).collect()
+    }).collect()
+}
+
+//water_auto_iter
+//Purpose:
+//    Returns the water after one iteration of the water automaton.
+//Notes:
+//  Floor cells with water on at least half their sides fill up, and water with at most one
+//    neighbor of water drains away unless it is hemmed in by walls.
+fn water_auto_iter(my_rows: usize, my_columns: usize, wrap: usize, water_matrix: &[Vec<bool>], cave_matrix: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let mut next_matrix = water_matrix.to_vec();
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            if !cave_matrix[i][j] {
+                let water_ratio = neighbor_wall_ratio(my_rows,my_columns,wrap,i,j,water_matrix);
+                if water_ratio >= 0.5 {
+                    next_matrix[i][j] = true;
+                } else if (water_ratio <= 0.125) && (neighbor_wall_ratio(my_rows,my_columns,wrap,i,j,cave_matrix) < WATER_ENCLOSURE) {
+                    next_matrix[i][j] = false;
+                }
+            }
+        }
+    }
+    next_matrix
+}
+
+//get_cave_neighbors
+//Purpose:
+//    Returns the cells sharing an edge with the cell (row, col).
+//Notes:
+//  Wrap indicates if the rectangle should be considered as having its sides meet up.
+//    A wrap of 0 means no wrapping, 1 means the east and west sides meet, and 2 means wrapping on both pairs of sides.
+//  A cave one cell across wraps back onto the cell itself, which is left out.
+fn get_cave_neighbors(max_rows: usize, max_cols: usize, row: usize, col: usize, wrap: usize) -> Vec<(usize,usize)> {
+    let mut neighbors = Vec::new();
+    for &step in [0,2].iter() {
+        if let Some(new_row) = shift_index(max_rows,row,step,wrap >= 2) {
+            if new_row != row {
+                neighbors.push((new_row,col));
+            }
+        }
+        if let Some(new_col) = shift_index(max_cols,col,step,wrap >= 1) {
+            if new_col != col {
+                neighbors.push((row,new_col));
+            }
+        }
+    }
+    neighbors
+}
 
+//shift_index
+//Purpose:
+//    Returns index + step - 1 if it lies in 0..max, so a step of 0, 1 or 2 moves back one, stays, or moves forward one.
+//Notes:
+//  If the index would leave the range it wraps to the other side when wraps is true, and is None otherwise.
+fn shift_index(max: usize, index: usize, step: usize, wraps: bool) -> Option<usize> {
+    let shifted = index + step; // usize, so must make sure not to subtract from zero
+    if (shifted >= 1) && (shifted < max + 1) {
+        Some(shifted - 1)
+    } else if wraps {
+        Some((shifted + max - 1) % max)
+    } else {
+        None
+    }
+}
+
+////////////////////
+//Chunked world code
+////////////////////
+
+//CaveWorld
+//Purpose:
+//    To describe a cave too large to hold in memory, which is generated one chunk at a time.
+//Notes:
+//  Every cell of the world starts as a wall or floor decided only by the seed and its position,
+//    so any chunk can be generated on its own and will agree with its neighbors along their borders.
+//  Chunk x counts chunks east and chunk y counts chunks north, in the same way as a MazeWorld.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaveWorld {
+    pub chunk_rows: usize,
+    pub chunk_columns: usize,
+    pub wall_prob: f64,
+    pub seed: u64,
+}
+
+impl CaveWorld {
+
+    pub fn init(chunk_rows: usize, chunk_columns: usize, seed: u64) -> Result<CaveWorld,CaveError> {
+		if (chunk_rows == 0) || (chunk_columns == 0) {
+			return Err(CaveError::Syntax("A chunk requires non-zero dimensions.".to_string()));
+        }
+        Ok(CaveWorld{chunk_rows, chunk_columns, wall_prob: DEFULT_WALL_PROB, seed})
+    }
+
+    //get_chunk
+    //Purpose:
+    //    Creates the chunk at (chunk_x, chunk_y), where chunk_x counts chunks east and chunk_y counts chunks north.
+    //Notes:
+    //  Rows of a cave run down the page, so the chunk north of another holds the rows just above its first row.
+    //  The automaton is run on the chunk together with a margin of NUM_OF_ITERS cells on every side.
+    //    Each synchronous iteration only looks one cell further out, so after cutting the margin away
+    //    the chunk is exactly what it would have been had the whole world been generated at once.
+    pub fn get_chunk(&self, chunk_x: i64, chunk_y: i64) -> Cave {
+        let margin = NUM_OF_ITERS;
+        let padded_rows = self.chunk_rows + 2*margin;
+        let padded_columns = self.chunk_columns + 2*margin;
+        let first_row = -chunk_y*(self.chunk_rows as i64) - (margin as i64);
+        let first_column = chunk_x*(self.chunk_columns as i64) - (margin as i64);
+        let mut padded_matrix = vec![vec![false; padded_columns]; padded_rows];
+        for (i, padded_row) in padded_matrix.iter_mut().enumerate() {
+            for (j, cell) in padded_row.iter_mut().enumerate() {
+                *cell = self.get_starting_wall(first_row + i as i64, first_column + j as i64);
+            }
+        }
+        let condition_grid = vec![vec![1; padded_columns]; padded_rows];
         for _i in 0..NUM_OF_ITERS {
-            cell_auto_iter(my_rows, my_columns, &mut matrix, &condition_grid)
+            cell_auto_iter(padded_rows, padded_columns, 0, &mut padded_matrix, &condition_grid, UpdateMode::Synchronous);
         }
+        let matrix: Vec<Vec<bool>> = padded_matrix[margin..(margin + self.chunk_rows)].iter()
+            .map(|padded_row| padded_row[margin..(margin + self.chunk_columns)].to_vec()).collect();
+        Cave::from_walls(&matrix, 0)
+    }
 
-		Ok(Cave{rows: my_rows, columns: my_columns, cave_matrix: matrix})
+    //get_starting_wall
+    //Purpose:
+    //    Returns if the cell of the world at (row, column) starts as a wall before the automaton is run.
+    fn get_starting_wall(&self, row: i64, column: i64) -> bool {
+        let unit_value = (hash_lattice(column, row, self.seed) >> 11) as f64/((1u64 << 53) as f64);
+        unit_value <= self.wall_prob
     }
 
 }
 
-fn init_cave_matrix(my_rows: usize, my_columns: usize, wall_prob: f64, condition_grid: &Vec<Vec<usize>>) -> Vec<Vec<bool>> {
+////////////////////
+//Condition grid code
+////////////////////
+
+//read_condition_grid
+//Purpose:
+//    Reads a condition grid from a file, using the image reader for image files and the text reader otherwise.
+pub fn read_condition_grid(file_name: &str) -> Result<Vec<Vec<usize>>,CaveError> {
+    let extension = Path::new(file_name).extension().and_then(|ext| ext.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "png" | "jpeg" | "jpg" | "bmp" | "gif" => read_condition_grid_image(file_name),
+        _ => read_condition_grid_text(file_name),
+    }
+}
+
+//read_condition_grid_text
+//Purpose:
+//    Reads a condition grid from a text file where '#' is a forced wall, '.' is forced floor and '?' is random.
+//Notes:
+//  The first line of the file is row 0, matching the output of the text cave printer.
+//  Every line must have the same length, and blank lines at the end of the file are ignored.
+pub fn read_condition_grid_text(file_name: &str) -> Result<Vec<Vec<usize>>,CaveError> {
+    let contents = fs::read_to_string(file_name)?;
+    parse_condition_grid(&contents)
+}
+
+//parse_condition_grid
+//Purpose:
+//    Reads a condition grid from the text of a mask, as described for read_condition_grid_text.
+pub fn parse_condition_grid(text: &str) -> Result<Vec<Vec<usize>>,CaveError> {
+    let mut condition_grid = Vec::new();
+    for (line_number, line) in text.trim_end().lines().enumerate() {
+        let mut grid_row = Vec::new();
+        for symbol in line.trim_end_matches('\r').chars() {
+            match symbol {
+                '.' => grid_row.push(0),
+                '?' => grid_row.push(1),
+                '#' => grid_row.push(2),
+                _ => return Err(CaveError::Syntax(format!("Unexpected character '{}' on line {} of the mask.", symbol, line_number + 1))),
+            }
+        }
+        condition_grid.push(grid_row);
+    }
+    check_condition_grid(&condition_grid)?;
+    Ok(condition_grid)
+}
+
+//read_condition_grid_image
+//Purpose:
+//    Reads a condition grid from an image where black is a forced wall, white is forced floor and grey is random.
+//Notes:
+//  Each pixel is one cell, the top row of pixels is row 0.
+//  Colors are judged by brightness, so anything dark counts as black and anything light counts as white.
+pub fn read_condition_grid_image(file_name: &str) -> Result<Vec<Vec<usize>>,CaveError> {
+    let imgbuf = image::open(file_name)?.to_luma();
+    condition_grid_from_luma(&imgbuf)
+}
+
+//condition_grid_from_luma
+//Purpose:
+//    Reads a condition grid from the brightness of an image, as described for read_condition_grid_image.
+pub fn condition_grid_from_luma(imgbuf: &image::GrayImage) -> Result<Vec<Vec<usize>>,CaveError> {
+    let mut condition_grid = vec![vec![1; imgbuf.width() as usize]; imgbuf.height() as usize];
+    for (x, y, pixel) in imgbuf.enumerate_pixels() {
+        if pixel.0[0] < MASK_WALL_LUMA {
+            condition_grid[y as usize][x as usize] = 2;
+        } else if pixel.0[0] >= MASK_FLOOR_LUMA {
+            condition_grid[y as usize][x as usize] = 0;
+        }
+    }
+    check_condition_grid(&condition_grid)?;
+    Ok(condition_grid)
+}
+
+//scale_condition_grid
+//Purpose:
+//    Stretches a condition grid to the given dimensions, so a small sketch can be used for a large cave.
+//Notes:
+//  Each cell takes the value of the nearest cell of the original grid.
+//  The condition grid must be non-empty and rectangular, and the variables my_rows and my_columns non-zero.
+pub fn scale_condition_grid(condition_grid: &[Vec<usize>], my_rows: usize, my_columns: usize) -> Result<Vec<Vec<usize>>,CaveError> {
+    check_condition_grid(condition_grid)?;
+    if (my_rows == 0) || (my_columns == 0) {
+		return Err(CaveError::Syntax("A mask can only be scaled to non-zero dimensions.".to_string()));
+    }
+    let grid_rows = condition_grid.len();
+    let grid_columns = condition_grid[0].len();
+    let mut scaled_grid = vec![vec![1; my_columns]; my_rows];
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            scaled_grid[i][j] = condition_grid[(i*grid_rows)/my_rows][(j*grid_columns)/my_columns];
+        }
+    }
+    Ok(scaled_grid)
+}
+
+fn check_condition_grid(condition_grid: &[Vec<usize>]) -> Result<(),CaveError> {
+    if condition_grid.is_empty() || condition_grid[0].is_empty() {
+        return Err(CaveError::Syntax("The mask is empty.".to_string()));
+    }
+    for grid_row in condition_grid.iter() {
+        if grid_row.len() != condition_grid[0].len() {
+            return Err(CaveError::Syntax("Every row of the mask must have the same length.".to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn init_cave_matrix(my_rows: usize, my_columns: usize, wall_prob: f64, condition_grid: &[Vec<usize>]) -> Vec<Vec<bool>> {
     let mut cave_matrix = vec![vec![false; my_columns]; my_rows];
     for i in 0..my_rows {
         for j in 0..my_columns {
@@ -103,17 +852,194 @@ fn init_cave_matrix(my_rows: usize, my_columns: usize, wall_prob: f64, condition
             }
         }
     }
-    return cave_matrix;
+    cave_matrix
+}
+
+//init_noise_matrix
+//Purpose:
+//    Returns a cave where the walls are wherever the noise is at least the threshold.
+//Notes:
+//  Cells with a condition of 0 or 2 are floor or wall regardless of the noise.
+fn init_noise_matrix(my_rows: usize, my_columns: usize, wrap: usize, settings: &NoiseSettings, condition_grid: &[Vec<usize>]) -> Result<Vec<Vec<bool>>,NoiseError> {
+    let noise = noise_grid(my_rows, my_columns, wrap, settings)?;
+    let mut cave_matrix = vec![vec![false; my_columns]; my_rows];
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            cave_matrix[i][j] = match condition_grid[i][j] {
+                0 => false,
+                2 => true,
+                _ => noise[i][j] >= settings.threshold,
+            };
+        }
+    }
+    Ok(cave_matrix)
+}
+
+//drunkards_walk
+//Purpose:
+//    Returns a cave dug out by random walkers until floor_ratio of the cave is floor.
+//Notes:
+//  The first walker starts from a random cell which may be dug, and every later walker starts from a cell already dug,
+//    so everything the walkers dig is connected.
+//  Walkers never step onto cells with a condition of 2.
+//  If the walkers can not reach enough cells the cave is returned once they have had DIG_STEP_LIMIT steps per cell.
+fn drunkards_walk(my_rows: usize, my_columns: usize, wrap: usize, floor_ratio: f64, condition_grid: &[Vec<usize>]) -> Vec<Vec<bool>> {
+    let (mut cave_matrix, mut floor_count, target) = init_dig_matrix(my_rows, my_columns, floor_ratio, condition_grid);
+    let mut dug_cells = Vec::new();
+    if let Some(start) = get_random_diggable_cell(my_rows, my_columns, condition_grid) {
+        cave_matrix[start.0][start.1] = false;
+        floor_count += 1;
+        dug_cells.push(start);
+    }
+    let mut steps_left = DIG_STEP_LIMIT*my_rows*my_columns;
+    while (floor_count < target) && (steps_left > 0) && (!dug_cells.is_empty()) {
+        let mut current_cell = dug_cells[rand::thread_rng().gen_range(0, dug_cells.len())];
+        for _i in 0..DRUNKARD_WALK_LENGTH {
+            if (floor_count >= target) || (steps_left == 0) {
+                break;
+            }
+            steps_left -= 1;
+            let neighbors: Vec<(usize,usize)> = get_cave_neighbors(my_rows, my_columns, current_cell.0, current_cell.1, wrap).into_iter()
+                .filter(|&(row,col)| condition_grid[row][col] != 2).collect();
+            if neighbors.is_empty() { //the walker is boxed in by forced walls.
+                break;
+            }
+            current_cell = neighbors[rand::thread_rng().gen_range(0, neighbors.len())];
+            if cave_matrix[current_cell.0][current_cell.1] {
+                cave_matrix[current_cell.0][current_cell.1] = false;
+                floor_count += 1;
+                dug_cells.push(current_cell);
+            }
+        }
+    }
+    cave_matrix
+}
+
+//diffusion_limited_aggregation
+//Purpose:
+//    Returns a cave grown by particles which wander through the rock until they touch the floor and stick to it.
+//Notes:
+//  The cave grows from a random cell which may be dug, along with any cells with a condition of 0.
+//  Particles are released a few cells around the cave grown so far, never step onto cells with
+//    a condition of 2, and are released again from a new cell if they wander for too long.
+//  If the particles can not reach enough cells the cave is returned once they have had DIG_STEP_LIMIT steps per cell.
+fn diffusion_limited_aggregation(my_rows: usize, my_columns: usize, wrap: usize, floor_ratio: f64, condition_grid: &[Vec<usize>]) -> Vec<Vec<bool>> {
+    let (mut cave_matrix, mut floor_count, target) = init_dig_matrix(my_rows, my_columns, floor_ratio, condition_grid);
+    if let Some(seed) = get_random_diggable_cell(my_rows, my_columns, condition_grid) {
+        cave_matrix[seed.0][seed.1] = false;
+        floor_count += 1;
+    }
+    //particles are released close to the cave, within a box around the floor found so far.
+    let (mut min_row, mut max_row, mut min_col, mut max_col) = (my_rows, 0, my_columns, 0);
+    for (i, cave_row) in cave_matrix.iter().enumerate() {
+        for (j, &is_wall) in cave_row.iter().enumerate() {
+            if !is_wall {
+                min_row = min_row.min(i);
+                max_row = max_row.max(i);
+                min_col = min_col.min(j);
+                max_col = max_col.max(j);
+            }
+        }
+    }
+    let mut steps_left = DIG_STEP_LIMIT*my_rows*my_columns;
+    while (floor_count < target) && (steps_left > 0) && (min_row <= max_row) {
+        steps_left -= 1;
+        let row = rand::thread_rng().gen_range(min_row.saturating_sub(RELEASE_MARGIN), (max_row + RELEASE_MARGIN + 1).min(my_rows));
+        let col = rand::thread_rng().gen_range(min_col.saturating_sub(RELEASE_MARGIN), (max_col + RELEASE_MARGIN + 1).min(my_columns));
+        if (condition_grid[row][col] != 1) || !cave_matrix[row][col] { //released inside the cave or in forced rock, try again.
+            continue;
+        }
+        let mut particle = (row,col);
+        for _i in 0..(my_rows + my_columns)*(my_rows + my_columns) {
+            if steps_left == 0 {
+                break;
+            }
+            steps_left -= 1;
+            let neighbors = get_cave_neighbors(my_rows, my_columns, particle.0, particle.1, wrap);
+            if neighbors.iter().any(|&(row,col)| !cave_matrix[row][col]) { //the particle touches the cave, so it sticks.
+                cave_matrix[particle.0][particle.1] = false;
+                floor_count += 1;
+                min_row = min_row.min(particle.0);
+                max_row = max_row.max(particle.0);
+                min_col = min_col.min(particle.1);
+                max_col = max_col.max(particle.1);
+                break;
+            }
+            let moves: Vec<(usize,usize)> = neighbors.into_iter().filter(|&(row,col)| condition_grid[row][col] != 2).collect();
+            if moves.is_empty() {
+                break;
+            }
+            particle = moves[rand::thread_rng().gen_range(0, moves.len())];
+        }
+    }
+    cave_matrix
+}
+
+//init_dig_matrix
+//Purpose:
+//    Returns a cave of solid rock apart from the cells forced to be floor,
+//    along with the number of floor cells and the number of floor cells wanted.
+fn init_dig_matrix(my_rows: usize, my_columns: usize, floor_ratio: f64, condition_grid: &[Vec<usize>]) -> (Vec<Vec<bool>>,usize,usize) {
+    let mut cave_matrix = vec![vec![true; my_columns]; my_rows];
+    let mut floor_count = 0;
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            if condition_grid[i][j] == 0 {
+                cave_matrix[i][j] = false;
+                floor_count += 1;
+            }
+        }
+    }
+    let target = (floor_ratio*((my_rows*my_columns) as f64)).ceil() as usize;
+    (cave_matrix, floor_count, target)
 }
 
-fn cell_auto_iter(my_rows: usize, my_columns: usize, cave_matrix: &mut Vec<Vec<bool>>, condition_grid: &Vec<Vec<usize>>){
+//get_random_diggable_cell
+//Purpose:
+//    Returns a random cell with a condition of 1, or None if there are no such cells.
+fn get_random_diggable_cell(my_rows: usize, my_columns: usize, condition_grid: &[Vec<usize>]) -> Option<(usize,usize)> {
+    for _i in 0..(my_rows*my_columns) { //guess first, since most cells can usually be dug.
+        let row = rand::thread_rng().gen_range(0, my_rows);
+        let col = rand::thread_rng().gen_range(0, my_columns);
+        if condition_grid[row][col] == 1 {
+            return Some((row,col));
+        }
+    }
+    let mut diggable_cells = Vec::new();
+    for (i, condition_row) in condition_grid.iter().enumerate() {
+        for (j, &condition) in condition_row.iter().enumerate() {
+            if condition == 1 {
+                diggable_cells.push((i,j));
+            }
+        }
+    }
+    if diggable_cells.is_empty() {
+        return None;
+    }
+    Some(diggable_cells[rand::thread_rng().gen_range(0, diggable_cells.len())])
+}
+
+//cell_auto_iter
+//Purpose:
+//    Advances the cave by one iteration of the cellular automaton.
+//Notes:
+//  A synchronous update reads every neighbor from a copy of the previous generation,
+//    while an asynchronous update reads from the cave as it is being rewritten.
+fn cell_auto_iter(my_rows: usize, my_columns: usize, wrap: usize, cave_matrix: &mut [Vec<bool>], condition_grid: &[Vec<usize>], update_mode: UpdateMode){
+    let previous_matrix = match update_mode {
+        UpdateMode::Synchronous => Some(cave_matrix.to_vec()),
+        UpdateMode::Asynchronous => None,
+    };
     for i in 0..my_rows {
         for j in 0..my_columns {
             if condition_grid[i][j] == 1 { //If a wall is not predetmined to either be there or not, use cell laws to advance.
-                let neightbor_ratio = neighbor_wall_ratio(my_rows,my_columns,i,j,&cave_matrix);
-                if (neightbor_ratio >= 0.6) && (cave_matrix[i][j] == false) {
+                let neightbor_ratio = match previous_matrix {
+                    Some(ref old_matrix) => neighbor_wall_ratio(my_rows,my_columns,wrap,i,j,old_matrix),
+                    None => neighbor_wall_ratio(my_rows,my_columns,wrap,i,j,cave_matrix),
+                };
+                if (neightbor_ratio >= 0.6) && (!cave_matrix[i][j]) {
                     cave_matrix[i][j] = true;
-                }else if (neightbor_ratio < 0.5) && (cave_matrix[i][j] == true){
+                }else if (neightbor_ratio < 0.5) && (cave_matrix[i][j]){
                    cave_matrix[i][j] = false;
                 }
             }
@@ -121,20 +1047,357 @@ fn cell_auto_iter(my_rows: usize, my_columns: usize, cave_matrix: &mut Vec<Vec<b
     }
 }
 
-fn neighbor_wall_ratio(my_rows: usize, my_columns: usize, my_row: usize, my_column: usize, cave_matrix: &Vec<Vec<bool>>) -> f64{
+//neighbor_wall_ratio
+//Purpose:
+//    Returns the fraction of the eight cells surrounding (my_row, my_column) that are walls.
+//Notes:
+//  Neighbors outside the cave are not counted unless the cave wraps on that side.
+fn neighbor_wall_ratio(my_rows: usize, my_columns: usize, wrap: usize, my_row: usize, my_column: usize, cave_matrix: &[Vec<bool>]) -> f64{
     let mut neighbors = 0.0;
     let mut neighboring_walls = 0.0;
     for i in 0..3 {
         for j in 0..3 {
             if (i != 1) || (j != 1) {
-                if (my_row + i >= 1) && (my_row + i < my_rows + 1) && (my_column + j >= 1) && (my_column + j < my_columns + 1){ // usize, so must make sure not to subtract from zero
-                    neighbors = neighbors + 1.0;
-                    if cave_matrix[my_row + i-1][my_column+j-1] == true { //if there is a wall
-                        neighboring_walls = neighboring_walls + 1.0;
+                let row = shift_index(my_rows,my_row,i,wrap >= 2);
+                let column = shift_index(my_columns,my_column,j,wrap >= 1);
+                if let (Some(row),Some(column)) = (row,column) {
+                    if (row != my_row) || (column != my_column) { //a narrow wrapped cave can wrap back onto the cell itself.
+                        neighbors += 1.0;
+                        if cave_matrix[row][column] { //if there is a wall
+                            neighboring_walls += 1.0;
+                        }
                     }
                 }
             }
         }
     }
-    return neighboring_walls/neighbors;
+    neighboring_walls/neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::MAX_OCTAVES;
+    use rand::{SeedableRng, XorShiftRng};
+
+    //get_bordered_condition_grid
+    //Purpose:
+    //    Returns a condition grid with forced walls around the edge and a block of forced floor inside.
+    fn get_bordered_condition_grid(my_rows: usize, my_columns: usize) -> Vec<Vec<usize>> {
+        let mut condition_grid = vec![vec![1; my_columns]; my_rows];
+        for (i, grid_row) in condition_grid.iter_mut().enumerate() {
+            for (j, condition) in grid_row.iter_mut().enumerate() {
+                if (i == 0) || (j == 0) || (i == my_rows - 1) || (j == my_columns - 1) {
+                    *condition = 2;
+                } else if (i < 4) && (j < 4) {
+                    *condition = 0;
+                }
+            }
+        }
+        condition_grid
+    }
+
+    //assert_conditions_kept
+    //Purpose:
+    //    Checks that a cave has walls and floor wherever the condition grid forces them, and returns its number of floor cells.
+    fn assert_conditions_kept(cave_matrix: &[Vec<bool>], condition_grid: &[Vec<usize>]) -> usize {
+        let mut floor_count = 0;
+        for (i, cave_row) in cave_matrix.iter().enumerate() {
+            for (j, &is_wall) in cave_row.iter().enumerate() {
+                match condition_grid[i][j] {
+                    0 => assert!(!is_wall, "forced floor at ({}, {}) was filled", i, j),
+                    2 => assert!(is_wall, "forced wall at ({}, {}) was dug", i, j),
+                    _ => (),
+                }
+                if !is_wall {
+                    floor_count += 1;
+                }
+            }
+        }
+        floor_count
+    }
+
+    type Digger = fn(usize, usize, usize, f64, &[Vec<usize>]) -> Vec<Vec<bool>>;
+
+    #[test]
+    fn diggers_keep_the_conditions_and_reach_the_floor_ratio() {
+        let (rows, columns) = (20, 30);
+        let condition_grid = get_bordered_condition_grid(rows, columns);
+        let diggers: [Digger; 2] = [drunkards_walk, diffusion_limited_aggregation];
+        for (digger_number, digger) in diggers.iter().enumerate() {
+            for wrap in 0..3 {
+                let cave_matrix = digger(rows, columns, wrap, 0.4, &condition_grid);
+                //each cell is dug one at a time, so the diggers stop exactly on the target.
+                assert_eq!(assert_conditions_kept(&cave_matrix, &condition_grid), (0.4*((rows*columns) as f64)).ceil() as usize,
+                    "digger {} with wrap {}", digger_number, wrap);
+            }
+        }
+    }
+
+    #[test]
+    fn diggers_stop_when_the_floor_ratio_is_out_of_reach() {
+        //the forced walls take up more than a tenth of the cave, so a ratio of 0.9 can't be reached.
+        let (rows, columns) = (8, 10);
+        let condition_grid = get_bordered_condition_grid(rows, columns);
+        let diggable = condition_grid.iter().flatten().filter(|&&condition| condition != 2).count();
+        assert_eq!(assert_conditions_kept(&drunkards_walk(rows, columns, 0, 0.9, &condition_grid), &condition_grid), diggable);
+        assert!(assert_conditions_kept(&diffusion_limited_aggregation(rows, columns, 0, 0.9, &condition_grid), &condition_grid) <= diggable);
+    }
+
+    #[test]
+    fn text_masks_are_parsed() {
+        assert_eq!(parse_condition_grid("#?.\r\n..#\n\n").unwrap(), vec![vec![2,1,0], vec![0,0,2]]);
+        assert!(parse_condition_grid("#?.\n.x#").is_err());
+        assert!(parse_condition_grid("#?.\n.#").is_err());
+        assert!(parse_condition_grid("\n").is_err());
+    }
+
+    #[test]
+    fn image_masks_are_read_by_brightness() {
+        let imgbuf = image::GrayImage::from_fn(3, 2, |x, y| image::Luma([[0, 128, 255][((x + y) % 3) as usize]]));
+        assert_eq!(condition_grid_from_luma(&imgbuf).unwrap(), vec![vec![2,1,0], vec![1,0,2]]);
+    }
+
+    #[test]
+    fn masks_are_scaled_to_the_nearest_cell() {
+        let scaled = scale_condition_grid(&[vec![0,1], vec![2,1]], 4, 4).unwrap();
+        assert_eq!(scaled, vec![vec![0,0,1,1], vec![0,0,1,1], vec![2,2,1,1], vec![2,2,1,1]]);
+        assert!(scale_condition_grid(&[], 4, 4).is_err());
+        assert!(scale_condition_grid(&[vec![0,1], vec![2]], 4, 4).is_err());
+        assert!(scale_condition_grid(&[vec![0,1], vec![2,1]], 0, 4).is_err());
+    }
+
+    #[test]
+    fn shift_index_wraps_only_when_asked() {
+        assert_eq!(shift_index(5, 0, 0, false), None);
+        assert_eq!(shift_index(5, 0, 0, true), Some(4));
+        assert_eq!(shift_index(5, 4, 2, false), None);
+        assert_eq!(shift_index(5, 4, 2, true), Some(0));
+        assert_eq!(shift_index(5, 2, 1, true), Some(2));
+        assert_eq!(shift_index(5, 2, 2, false), Some(3));
+    }
+
+    #[test]
+    fn cave_neighbors_follow_the_wrap() {
+        let (rows, columns) = (4, 6);
+        for wrap in 0..3 {
+            let neighbors = get_cave_neighbors(rows, columns, 0, 0, wrap);
+            assert!(neighbors.contains(&(1,0)) && neighbors.contains(&(0,1)));
+            assert_eq!(neighbors.contains(&(0,columns - 1)), wrap >= 1, "wrap {}", wrap);
+            assert_eq!(neighbors.contains(&(rows - 1,0)), wrap == 2, "wrap {}", wrap);
+            assert_eq!(neighbors.len(), 2 + wrap);
+        }
+    }
+
+    #[test]
+    fn narrow_cave_cells_are_not_their_own_neighbors() {
+        for wrap in 0..3 {
+            assert_eq!(get_cave_neighbors(3, 1, 1, 0, wrap), vec![(0,0), (2,0)], "wrap {}", wrap);
+            assert!(!get_cave_neighbors(1, 1, 0, 0, wrap).contains(&(0,0)), "wrap {}", wrap);
+            assert!(!get_cave_neighbors(1, 4, 0, 2, wrap).contains(&(0,2)), "wrap {}", wrap);
+        }
+    }
+
+    //get_seeded_matrix
+    //Purpose:
+    //    Returns a starting cave with walls placed by a generator with a fixed seed.
+    fn get_seeded_matrix(my_rows: usize, my_columns: usize, seed: u32) -> Vec<Vec<bool>> {
+        let mut rng = XorShiftRng::from_seed([seed, 0x1234_5678, 0x9abc_def0, 1]);
+        (0..my_rows).map(|_| (0..my_columns).map(|_| rng.gen::<f64>() < DEFULT_WALL_PROB).collect()).collect()
+    }
+
+    #[test]
+    fn synchronous_and_asynchronous_updates_differ() {
+        let start = get_seeded_matrix(20, 20, 7);
+        let condition_grid = vec![vec![1; 20]; 20];
+        let mut synchronous = start.clone();
+        cell_auto_iter(20, 20, 0, &mut synchronous, &condition_grid, UpdateMode::Synchronous);
+        let mut asynchronous = start.clone();
+        cell_auto_iter(20, 20, 0, &mut asynchronous, &condition_grid, UpdateMode::Asynchronous);
+        assert_ne!(synchronous, asynchronous);
+
+        //every cell of the synchronous update follows from the starting cave alone.
+        for i in 0..20 {
+            for j in 0..20 {
+                let ratio = neighbor_wall_ratio(20, 20, 0, i, j, &start);
+                let expected = if ratio >= 0.6 { true } else if ratio < 0.5 { false } else { start[i][j] };
+                assert_eq!(synchronous[i][j], expected, "cell ({}, {})", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_match_a_single_larger_chunk() {
+        let world = CaveWorld::init(12, 10, 99).unwrap();
+        let wide_world = CaveWorld::init(12, 20, 99).unwrap();
+        let tall_world = CaveWorld::init(24, 10, 99).unwrap();
+
+        //chunk (1, 0) continues chunk (0, 0) to the east.
+        let wide = wide_world.get_chunk(0, 0);
+        let (west, east) = (world.get_chunk(0, 0), world.get_chunk(1, 0));
+        for i in 0..12 {
+            assert_eq!(wide.tile_matrix[i][..10], west.tile_matrix[i][..], "row {}", i);
+            assert_eq!(wide.tile_matrix[i][10..], east.tile_matrix[i][..], "row {}", i);
+        }
+
+        //chunk (0, -1) continues chunk (0, 0) to the south, below it on the page.
+        let tall = tall_world.get_chunk(0, 0);
+        let south = world.get_chunk(0, -1);
+        assert_eq!(tall.tile_matrix[..12], west.tile_matrix[..]);
+        assert_eq!(tall.tile_matrix[12..], south.tile_matrix[..]);
+    }
+
+    //get_text_cave
+    //Purpose:
+    //    Returns a cave drawn as text, where '#' is a wall and anything else is floor.
+    fn get_text_cave(lines: &[&str]) -> Cave {
+        let wall_matrix: Vec<Vec<bool>> = lines.iter().map(|line| line.chars().map(|symbol| symbol == '#').collect()).collect();
+        Cave::from_walls(&wall_matrix, 0)
+    }
+
+    const TWO_REGIONS: [&str; 5] = [
+        "#######",
+        "#..#..#",
+        "#..#..#",
+        "#..##.#",
+        "#######",
+    ];
+
+    #[test]
+    fn regions_are_labeled_and_ordered_by_size() {
+        let cave = get_text_cave(&TWO_REGIONS);
+        let label_matrix = cave.label_regions();
+        assert_eq!(label_matrix[0][0], None);
+        assert_eq!(label_matrix[1][1], Some(0));
+        assert_eq!(label_matrix[3][2], Some(0));
+        assert_eq!(label_matrix[1][4], Some(1));
+        assert_eq!(label_matrix[3][5], Some(1));
+
+        let regions = cave.get_regions();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].len(), 6);
+        assert_eq!(regions[1].len(), 5);
+        assert!(regions[1].contains(&(3,5)));
+    }
+
+    #[test]
+    fn small_regions_are_culled() {
+        let mut cave = get_text_cave(&TWO_REGIONS);
+        cave.cull_regions(5);
+        assert_eq!(cave.get_regions().len(), 2);
+        cave.cull_regions(6);
+        let regions = cave.get_regions();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].len(), 6);
+        assert!(cave.is_wall(3,5));
+        assert_eq!(cave.tile_matrix[3][5], Tile::Wall);
+    }
+
+    #[test]
+    fn regions_are_joined_by_the_shortest_tunnel() {
+        let mut cave = get_text_cave(&TWO_REGIONS);
+        cave.join_regions(&vec![vec![1; 7]; 5]);
+        assert_eq!(cave.get_regions().len(), 1);
+        let dug = (0..5).flat_map(|i| (0..7).map(move |j| (i,j))).filter(|&(i,j)| !cave.is_wall(i,j)).count();
+        assert_eq!(dug, 12);
+    }
+
+    #[test]
+    fn tunnels_avoid_forced_walls() {
+        let mut cave = get_text_cave(&TWO_REGIONS);
+        let mut condition_grid = vec![vec![1; 7]; 5];
+        condition_grid[1][3] = 2;
+        condition_grid[2][3] = 2;
+        cave.join_regions(&condition_grid);
+        assert_eq!(cave.get_regions().len(), 1);
+        assert!(cave.is_wall(1,3) && cave.is_wall(2,3));
+        assert!(!cave.is_wall(3,3) && !cave.is_wall(3,4));
+
+        //with every way between them forced, the regions stay apart.
+        let mut cave = get_text_cave(&TWO_REGIONS);
+        for condition_row in condition_grid.iter_mut() {
+            condition_row[3] = 2;
+        }
+        cave.join_regions(&condition_grid);
+        assert_eq!(cave.get_regions().len(), 2);
+    }
+
+    #[test]
+    fn materials_are_deterministic() {
+        let world = CaveWorld::init(30, 30, 11).unwrap();
+        let mut settings = MaterialSettings::init(23);
+        settings.ore_prob = 0.5;
+        let mut first = world.get_chunk(0, 0);
+        let mut second = world.get_chunk(0, 0);
+        first.place_materials(&settings);
+        second.place_materials(&settings);
+        assert!(first.tile_matrix.iter().flatten().any(|&tile| tile == Tile::Ore));
+        assert_eq!(first.tile_matrix, second.tile_matrix);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn cave_matrix_follows_the_tiles() {
+        let mut cave = CaveWorld::init(30, 30, 11).unwrap().get_chunk(0, 0);
+        let mut settings = MaterialSettings::init(23);
+        settings.ore_prob = 0.5;
+        cave.place_materials(&settings);
+        cave.cull_regions(10);
+        cave.tile_matrix[0][0] = Tile::Lava;
+        cave.tile_matrix[0][1] = Tile::Ore;
+        let cave_matrix = cave.cave_matrix();
+        assert!(!cave_matrix[0][0] && cave_matrix[0][1]);
+        let walls_from_tiles: Vec<Vec<bool>> = cave.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|tile| tile.is_wall()).collect()).collect();
+        assert_eq!(cave_matrix, walls_from_tiles);
+    }
+
+    #[test]
+    fn cave_generator_gives_one_event_per_iteration() {
+        let generator = CaveGenerator::init(12, 15, 0, vec![vec![1; 15]; 12], CaveMethod::CellularAutomaton(DEFULT_WALL_PROB,UpdateMode::Synchronous)).unwrap();
+        let events: Vec<GenerationEvent> = generator.collect();
+        let expected: Vec<GenerationEvent> = (1..=NUM_OF_ITERS).map(GenerationEvent::CaveIteration).collect();
+        assert_eq!(events, expected);
+
+        //methods which aren't smoothed by the automaton are finished at once.
+        let mut generator = CaveGenerator::init(12, 15, 0, vec![vec![1; 15]; 12], CaveMethod::DrunkardsWalk(0.4)).unwrap();
+        assert_eq!(generator.next(), None);
+    }
+
+    #[test]
+    fn noise_octaves_are_limited() {
+        let condition_grid = vec![vec![1; 10]; 10];
+        let mut settings = NoiseSettings::init(4.0, 3);
+        settings.octaves = MAX_OCTAVES + 1;
+        assert!(CaveGenerator::init(10, 10, 0, condition_grid.clone(), CaveMethod::Noise(settings,None)).is_err());
+        settings.octaves = 0;
+        assert!(CaveGenerator::init(10, 10, 0, condition_grid.clone(), CaveMethod::Noise(settings,None)).is_err());
+        settings.octaves = MAX_OCTAVES;
+        assert!(CaveGenerator::init(10, 10, 2, condition_grid, CaveMethod::Noise(settings,None)).is_ok());
+    }
+
+    #[test]
+    fn noise_threshold_sets_the_wall_fraction() {
+        let mut settings = NoiseSettings::init(5.0, 13);
+        let mut last_fraction = 1.0;
+        for &threshold in [0.0, 0.3, 0.45, 0.55, 0.7, 1.0].iter() {
+            settings.threshold = threshold;
+            let cave = Cave::init_cave_with_method(40, 40, 2, vec![vec![1; 40]; 40], CaveMethod::Noise(settings,None)).unwrap();
+            let walls = cave.get_wall_matrix().iter().flatten().filter(|&&is_wall| is_wall).count();
+            let fraction = (walls as f64)/1600.0;
+            if threshold == 0.0 {
+                assert_eq!(fraction, 1.0);
+            } else if threshold == 1.0 {
+                assert!(fraction < 0.01, "{} walls at threshold 1.0", walls);
+            } else {
+                assert!((fraction < last_fraction) && (fraction > 0.0), "fraction {} at threshold {}", fraction, threshold);
+            }
+            last_fraction = fraction;
+        }
+    }
+
+    #[test]
+    fn chunks_are_deterministic() {
+        let world = CaveWorld::init(15, 15, 5).unwrap();
+        assert_eq!(world.get_chunk(-3, 4).tile_matrix, world.get_chunk(-3, 4).tile_matrix);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,667 @@
+//Date Created: 10/18/2026
+//Purpose To create dungeons of rooms joined by winding corridors
+//Notes:
+//  The dungeon is laid out on a grid of tiles, where the tiles with two odd coordinates are the squares of a maze
+//    and the tiles between them are the walls of the maze, in the same way that the text output draws a maze.
+
+use rand::Rng;
+use crate::maze::{Maze, Direction, GenerationType};
+use crate::cave::{Cave, CaveMethod, CaveError};
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum DungeonError {
+    Syntax(String),
+    Cave(CaveError),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for DungeonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DungeonError::Syntax(ref err_string) => write!(f,"{}",err_string),
+            DungeonError::Cave(ref err) => write!(f,"{}",err),
+        }
+    }
+}
+
+impl Error for DungeonError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            DungeonError::Syntax(ref _err_string) => None,
+            DungeonError::Cave(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<CaveError> for DungeonError {
+    fn from(err: CaveError) -> DungeonError {
+        DungeonError::Cave(err)
+    }
+}
+
+////////////////////
+//Dungeon code
+////////////////////
+
+//DungeonTile
+//Purpose:
+//    To record what fills a tile of the dungeon.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DungeonTile {
+    Wall,
+    Room,
+    Corridor,
+    Door,
+}
+
+impl DungeonTile {
+
+    pub fn is_floor(self) -> bool {
+        self != DungeonTile::Wall
+    }
+
+    //glyph
+    //Purpose:
+    //    Returns the character used for the tile in text output.
+    pub fn glyph(self) -> char {
+        match self {
+            DungeonTile::Wall => '#',
+            DungeonTile::Room => '.',
+            DungeonTile::Corridor => ' ',
+            DungeonTile::Door => '+',
+        }
+    }
+}
+
+//Room
+//Purpose:
+//    To record where a room is, by its top left tile and its size in tiles.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Room {
+    pub row: usize,
+    pub col: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Room {
+
+    pub fn contains(self, row: usize, col: usize) -> bool {
+        (row >= self.row) && (row < self.row + self.height) && (col >= self.col) && (col < self.col + self.width)
+    }
+
+    //overlaps
+    //Purpose:
+    //    Returns true if the rooms share a tile, or are so close that there is no wall between them.
+    pub fn overlaps(self, other: Room) -> bool {
+        (self.row <= other.row + other.height) && (other.row <= self.row + self.height)
+            && (self.col <= other.col + other.width) && (other.col <= self.col + self.width)
+    }
+
+    //center
+    //Purpose:
+    //    Returns the tile in the middle of the room.
+    pub fn center(self) -> (usize,usize) {
+        (self.row + self.height/2, self.col + self.width/2)
+    }
+}
+
+//DungeonSettings
+//Purpose:
+//    To describe how a rooms and mazes dungeon is laid out.
+//Notes:
+//  Room_attempts is how many rooms are tried, rooms which would overlap an earlier room are thrown away.
+//  Room sizes are in tiles and are rounded up to be odd, so rooms line up with the corridors.
+//  Extra_connection_chance is the chance each spare connection between two regions is also made a door,
+//    which adds loops to the dungeon.
+//  Straightness is passed to the backtrack algorithm which carves the corridors.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DungeonSettings {
+    pub room_attempts: usize,
+    pub min_room_size: usize,
+    pub max_room_size: usize,
+    pub extra_connection_chance: f64,
+    pub straightness: f64,
+    pub prune_dead_ends: bool,
+}
+
+impl DungeonSettings {
+
+    pub fn init() -> DungeonSettings {
+        DungeonSettings{room_attempts: 200, min_room_size: 3, max_room_size: 9, extra_connection_chance: 0.05, straightness: 0.5, prune_dead_ends: true}
+    }
+
+}
+
+//BspSettings
+//Purpose:
+//    To describe how a binary space partition dungeon is laid out.
+//Notes:
+//  Min_leaf_size is the smallest height or width in tiles a leaf may be split down to, and must be at least 3.
+//  Each split divides a leaf at a random fraction of its length between min_split_ratio and max_split_ratio.
+//  Min_room_size is the smallest height or width in tiles a room is made, unless its leaf is too small for it.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BspSettings {
+    pub min_leaf_size: usize,
+    pub min_split_ratio: f64,
+    pub max_split_ratio: f64,
+    pub min_room_size: usize,
+}
+
+impl BspSettings {
+
+    pub fn init() -> BspSettings {
+        BspSettings{min_leaf_size: 8, min_split_ratio: 0.35, max_split_ratio: 0.65, min_room_size: 3}
+    }
+
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dungeon {
+    pub rows: usize,
+    pub columns: usize,
+    pub tile_matrix: Vec<Vec<DungeonTile>>,
+    pub rooms: Vec<Room>,
+    pub connections: Vec<(usize,usize)>,
+}
+
+impl Dungeon {
+
+    //init_rooms_and_mazes
+    //Purpose:
+    //    Creates a dungeon by placing rooms, filling the space between them with a maze,
+    //    joining everything together with doors, and then removing the dead ends of the maze.
+    //Pre-Conditions:
+    //    The variables my_rows and my_columns are at least 3.
+    //Notes:
+    //  If the number of rows or columns is even, the last row or column is left as wall.
+    pub fn init_rooms_and_mazes(my_rows: usize, my_columns: usize, settings: &DungeonSettings) -> Result<Dungeon,DungeonError> {
+		if (my_rows < 3) || (my_columns < 3) {
+			return Err(DungeonError::Syntax("A dungeon must be at least 3 tiles in each dimension.".to_string()));
+        }
+        if (settings.min_room_size == 0) || (settings.min_room_size > settings.max_room_size) {
+			return Err(DungeonError::Syntax("Rooms require a positive minimum size no larger than the maximum size.".to_string()));
+        }
+        let maze_rows = (my_rows - 1)/2;
+        let maze_columns = (my_columns - 1)/2;
+        let mut dungeon = Dungeon{rows: my_rows, columns: my_columns, tile_matrix: vec![vec![DungeonTile::Wall; my_columns]; my_rows], rooms: Vec::new(), connections: Vec::new()};
+
+        dungeon.place_rooms(maze_rows, maze_columns, settings);
+        let bitmask = dungeon.get_free_cells(maze_rows, maze_columns);
+        dungeon.carve_corridors(&bitmask, settings.straightness);
+        dungeon.connect_regions(settings.extra_connection_chance);
+        if settings.prune_dead_ends {
+            dungeon.prune_dead_ends();
+        }
+        Ok(dungeon)
+    }
+
+    //init_hybrid
+    //Purpose:
+    //    Creates a level mixing a cave with a maze, where the cave is grown on the squares of the maze grid.
+    //Pre-Conditions:
+    //    The variables my_rows and my_columns are at least 3.
+    //Notes:
+    //  If maze_on_floor is false the floor of the cave is opened up as caverns (room tiles),
+    //    the rock between them is filled with a labyrinth of corridors, and the two are joined with doors
+    //    in the same way as the rooms and mazes generator, with the settings' room options unused.
+    //  If maze_on_floor is true the regions of the cave are first joined, and then the labyrinth is carved
+    //    inside the cave floor instead, giving a maze shaped like the cave.
+    //    Dead ends are never pruned in this case, since pruning a perfect maze would fill it in entirely.
+    pub fn init_hybrid(my_rows: usize, my_columns: usize, cave_method: CaveMethod, settings: &DungeonSettings, maze_on_floor: bool) -> Result<Dungeon,DungeonError> {
+		if (my_rows < 3) || (my_columns < 3) {
+			return Err(DungeonError::Syntax("A dungeon must be at least 3 tiles in each dimension.".to_string()));
+        }
+        let maze_rows = (my_rows - 1)/2;
+        let maze_columns = (my_columns - 1)/2;
+        let mut dungeon = Dungeon{rows: my_rows, columns: my_columns, tile_matrix: vec![vec![DungeonTile::Wall; my_columns]; my_rows], rooms: Vec::new(), connections: Vec::new()};
+        let condition_grid = vec![vec![1; maze_columns]; maze_rows];
+        let mut cave = Cave::init_cave_with_method(maze_rows, maze_columns, 0, condition_grid.clone(), cave_method)?;
+
+        if maze_on_floor {
+            cave.join_regions(&condition_grid);
+            dungeon.carve_corridors(&cave.floor_mask(), settings.straightness);
+        } else {
+            dungeon.open_caverns(&cave);
+            let rock_mask = cave.get_wall_matrix();
+            dungeon.carve_corridors(&rock_mask, settings.straightness);
+            dungeon.connect_regions(settings.extra_connection_chance);
+            if settings.prune_dead_ends {
+                dungeon.prune_dead_ends();
+            }
+        }
+        Ok(dungeon)
+    }
+
+    //open_caverns
+    //Purpose:
+    //    Turns the squares of the maze grid which are cave floor into room tiles,
+    //    along with the walls between floor squares so each region of the cave is one open cavern.
+    fn open_caverns(&mut self, cave: &Cave) {
+        for i in 0..cave.rows {
+            for j in 0..cave.columns {
+                if !cave.is_wall(i,j) {
+                    let east_open = (j + 1 < cave.columns) && !cave.is_wall(i,j+1);
+                    let south_open = (i + 1 < cave.rows) && !cave.is_wall(i+1,j);
+                    self.tile_matrix[2*i + 1][2*j + 1] = DungeonTile::Room;
+                    if east_open {
+                        self.tile_matrix[2*i + 1][2*j + 2] = DungeonTile::Room;
+                    }
+                    if south_open {
+                        self.tile_matrix[2*i + 2][2*j + 1] = DungeonTile::Room;
+                    }
+                    if east_open && south_open && !cave.is_wall(i+1,j+1) { //avoid leaving pillars inside open floor.
+                        self.tile_matrix[2*i + 2][2*j + 2] = DungeonTile::Room;
+                    }
+                }
+            }
+        }
+    }
+
+    //init_bsp
+    //Purpose:
+    //    Creates a dungeon by recursively splitting the map in two, placing one room in each leaf of the splits,
+    //    and joining the rooms of sibling leaves with L-shaped corridors.
+    //Pre-Conditions:
+    //    The variables my_rows and my_columns are at least min_leaf_size + 2.
+    //Notes:
+    //  The connections of the dungeon list each pair of rooms joined by a corridor, as indices into its rooms.
+    //    Corridors may pass through other rooms on their way, which adds shortcuts not listed in the connections.
+    pub fn init_bsp(my_rows: usize, my_columns: usize, settings: &BspSettings) -> Result<Dungeon,DungeonError> {
+        if settings.min_leaf_size < 3 {
+			return Err(DungeonError::Syntax("The minimum leaf size must be at least 3.".to_string()));
+        }
+        if (my_rows < settings.min_leaf_size + 2) || (my_columns < settings.min_leaf_size + 2) {
+			return Err(DungeonError::Syntax("The dungeon must be at least 2 tiles larger than the minimum leaf size in each dimension.".to_string()));
+        }
+        if !((settings.min_split_ratio > 0.0) && (settings.min_split_ratio <= settings.max_split_ratio) && (settings.max_split_ratio < 1.0)) {
+			return Err(DungeonError::Syntax("The split ratios must satisfy 0 < min <= max < 1.".to_string()));
+        }
+        let mut dungeon = Dungeon{rows: my_rows, columns: my_columns, tile_matrix: vec![vec![DungeonTile::Wall; my_columns]; my_rows], rooms: Vec::new(), connections: Vec::new()};
+        //the leaves cover everything but the top and left border, each room leaves the last row and column of its leaf as wall.
+        let root = Room{row: 1, col: 1, height: my_rows - 1, width: my_columns - 1};
+        dungeon.split_leaf(root, settings);
+        Ok(dungeon)
+    }
+
+    //split_leaf
+    //Purpose:
+    //    Splits the leaf in two if it is large enough, and places a room in it otherwise.
+    //    Returns the indices of the rooms made inside the leaf.
+    //Notes:
+    //  Long leaves are split across their length, so leaves stay close to square.
+    fn split_leaf(&mut self, leaf: Room, settings: &BspSettings) -> Vec<usize> {
+        let can_split_rows = leaf.height >= 2*settings.min_leaf_size;
+        let can_split_columns = leaf.width >= 2*settings.min_leaf_size;
+        let split_rows = match (can_split_rows, can_split_columns) {
+            (false,false) => return vec![self.place_leaf_room(leaf, settings)],
+            (true,false) => true,
+            (false,true) => false,
+            (true,true) => {
+                if (leaf.height as f64) > 1.25*(leaf.width as f64) {
+                    true
+                } else if (leaf.width as f64) > 1.25*(leaf.height as f64) {
+                    false
+                } else {
+                    rand::random::<bool>()
+                }
+            },
+        };
+        let length = if split_rows { leaf.height } else { leaf.width };
+        let ratio = rand::thread_rng().gen_range(settings.min_split_ratio, settings.max_split_ratio + f64::EPSILON);
+        let split = (((length as f64)*ratio).round() as usize).clamp(settings.min_leaf_size, length - settings.min_leaf_size);
+        let (first, second) = if split_rows {
+            (Room{height: split, ..leaf}, Room{row: leaf.row + split, height: leaf.height - split, ..leaf})
+        } else {
+            (Room{width: split, ..leaf}, Room{col: leaf.col + split, width: leaf.width - split, ..leaf})
+        };
+        let first_rooms = self.split_leaf(first, settings);
+        let second_rooms = self.split_leaf(second, settings);
+        self.connect_closest(&first_rooms, &second_rooms);
+        let mut rooms = first_rooms;
+        rooms.extend(second_rooms);
+        rooms
+    }
+
+    //place_leaf_room
+    //Purpose:
+    //    Places a room of random size inside the leaf, keeping the last row and column of the leaf as wall.
+    //    Returns the index of the new room.
+    fn place_leaf_room(&mut self, leaf: Room, settings: &BspSettings) -> usize {
+        let max_height = leaf.height - 1;
+        let max_width = leaf.width - 1;
+        let height = rand::thread_rng().gen_range(settings.min_room_size.clamp(1, max_height), max_height + 1);
+        let width = rand::thread_rng().gen_range(settings.min_room_size.clamp(1, max_width), max_width + 1);
+        let row = leaf.row + rand::thread_rng().gen_range(0, max_height - height + 1);
+        let col = leaf.col + rand::thread_rng().gen_range(0, max_width - width + 1);
+        let room = Room{row, col, height, width};
+        for i in room.row..(room.row + room.height) {
+            for j in room.col..(room.col + room.width) {
+                self.tile_matrix[i][j] = DungeonTile::Room;
+            }
+        }
+        self.rooms.push(room);
+        self.rooms.len() - 1
+    }
+
+    //connect_closest
+    //Purpose:
+    //    Joins the closest pair of rooms, one from each list, with an L-shaped corridor.
+    fn connect_closest(&mut self, first_rooms: &[usize], second_rooms: &[usize]) {
+        let mut best = (first_rooms[0], second_rooms[0]);
+        let mut best_distance = usize::MAX;
+        for &a in first_rooms {
+            for &b in second_rooms {
+                let (a_row, a_col) = self.rooms[a].center();
+                let (b_row, b_col) = self.rooms[b].center();
+                let distance = a_row.abs_diff(b_row) + a_col.abs_diff(b_col);
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = (a,b);
+                }
+            }
+        }
+        let start = self.rooms[best.0].center();
+        let end = self.rooms[best.1].center();
+        //the corridor turns at one of the two corners of the rectangle between the centers.
+        let corner = if rand::random::<bool>() { (start.0, end.1) } else { (end.0, start.1) };
+        self.carve_line(start, corner);
+        self.carve_line(corner, end);
+        self.connections.push(best);
+    }
+
+    //carve_line
+    //Purpose:
+    //    Turns the wall tiles on the straight line between two tiles in the same row or column into corridor.
+    fn carve_line(&mut self, start: (usize,usize), end: (usize,usize)) {
+        for i in start.0.min(end.0)..=start.0.max(end.0) {
+            for j in start.1.min(end.1)..=start.1.max(end.1) {
+                if self.tile_matrix[i][j] == DungeonTile::Wall {
+                    self.tile_matrix[i][j] = DungeonTile::Corridor;
+                }
+            }
+        }
+    }
+
+    //place_rooms
+    //Purpose:
+    //    Places rooms at random, throwing away any which would overlap a room already placed.
+    //Notes:
+    //  Rooms cover whole squares of the maze grid, so they start on odd tiles and have odd sizes.
+    fn place_rooms(&mut self, maze_rows: usize, maze_columns: usize, settings: &DungeonSettings) {
+        let min_cells = (settings.min_room_size + 1)/2;
+        let max_cells = (settings.max_room_size + 1)/2;
+        for _i in 0..settings.room_attempts {
+            let height_cells = rand::thread_rng().gen_range(min_cells, max_cells + 1);
+            let width_cells = rand::thread_rng().gen_range(min_cells, max_cells + 1);
+            if (height_cells > maze_rows) || (width_cells > maze_columns) {
+                continue;
+            }
+            let cell_row = rand::thread_rng().gen_range(0, maze_rows - height_cells + 1);
+            let cell_col = rand::thread_rng().gen_range(0, maze_columns - width_cells + 1);
+            let room = Room{row: 2*cell_row + 1, col: 2*cell_col + 1, height: 2*height_cells - 1, width: 2*width_cells - 1};
+            if self.rooms.iter().all(|other| !room.overlaps(*other)) {
+                for i in room.row..(room.row + room.height) {
+                    for j in room.col..(room.col + room.width) {
+                        self.tile_matrix[i][j] = DungeonTile::Room;
+                    }
+                }
+                self.rooms.push(room);
+            }
+        }
+    }
+
+    //get_free_cells
+    //Purpose:
+    //    Returns a matrix over the squares of the maze grid which is true on the squares not covered by a room.
+    fn get_free_cells(&self, maze_rows: usize, maze_columns: usize) -> Vec<Vec<bool>> {
+        let mut bitmask = vec![vec![true; maze_columns]; maze_rows];
+        for room in self.rooms.iter() {
+            for bitmask_row in bitmask[(room.row/2)..((room.row + room.height)/2)].iter_mut() {
+                for is_open in bitmask_row[(room.col/2)..((room.col + room.width)/2)].iter_mut() {
+                    *is_open = false;
+                }
+            }
+        }
+        bitmask
+    }
+
+    //carve_corridors
+    //Purpose:
+    //    Fills every square of the maze grid allowed by the bitmask with corridors, using the backtrack algorithm.
+    //Pre-Conditions:
+    //    The bitmask has (rows - 1)/2 rows and (columns - 1)/2 columns, one entry for each square of the maze grid.
+    fn carve_corridors(&mut self, bitmask: &[Vec<bool>], straightness: f64) {
+        let maze_rows = bitmask.len();
+        let maze_columns = bitmask[0].len();
+        //safe to unwrap since the bitmask was made with the maze's dimensions.
+        let corridors = Maze::init_rect_with_bitmask(maze_rows, maze_columns, 0, bitmask.to_vec(), GenerationType::Backtrack(straightness)).unwrap();
+        for (i, bitmask_row) in bitmask.iter().enumerate() {
+            for (j, &is_open) in bitmask_row.iter().enumerate() {
+                if is_open {
+                    self.tile_matrix[2*i + 1][2*j + 1] = DungeonTile::Corridor;
+                    if corridors.maze_matrix[i][j].has_dir(Direction::East) {
+                        self.tile_matrix[2*i + 1][2*j + 2] = DungeonTile::Corridor;
+                    }
+                    if corridors.maze_matrix[i][j].has_dir(Direction::North) {
+                        self.tile_matrix[2*i + 2][2*j + 1] = DungeonTile::Corridor;
+                    }
+                }
+            }
+        }
+    }
+
+    //connect_regions
+    //Purpose:
+    //    Opens doors in the walls between regions until every region is joined to the others.
+    //Notes:
+    //  A connector is a wall tile with floor from two different regions on opposite sides.
+    //  Connectors are opened at random, and once two regions are joined the other connectors between them
+    //    are only opened with probability extra_connection_chance.
+    fn connect_regions(&mut self, extra_connection_chance: f64) {
+        let label_matrix = self.label_regions();
+        let region_count = label_matrix.iter().flatten().filter_map(|label| *label).max().map_or(0, |label| label + 1);
+        //merged[label] is the region that the region with that label has been joined to.
+        let mut merged: Vec<usize> = (0..region_count).collect();
+        let mut connectors = Vec::new();
+        for i in 1..(self.rows - 1) {
+            for j in 1..(self.columns - 1) {
+                if self.tile_matrix[i][j] == DungeonTile::Wall {
+                    for &(first,second) in [((i-1,j),(i+1,j)),((i,j-1),(i,j+1))].iter() {
+                        if let (Some(a),Some(b)) = (label_matrix[first.0][first.1], label_matrix[second.0][second.1]) {
+                            if a != b {
+                                connectors.push((i,j,a,b));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        while !connectors.is_empty() {
+            let choice = rand::thread_rng().gen_range(0, connectors.len());
+            let (row, col, a, b) = connectors.swap_remove(choice);
+            self.tile_matrix[row][col] = DungeonTile::Door;
+            let (kept, joined) = (merged[a], merged[b]);
+            for region in merged.iter_mut() {
+                if *region == joined {
+                    *region = kept;
+                }
+            }
+            let mut remaining = Vec::new();
+            for connector in connectors.into_iter() {
+                if merged[connector.2] != merged[connector.3] {
+                    remaining.push(connector);
+                } else if rand::thread_rng().gen_range(0.0, 1.0) < extra_connection_chance && !self.is_next_to_door(connector.0, connector.1) {
+                    self.tile_matrix[connector.0][connector.1] = DungeonTile::Door;
+                }
+            }
+            connectors = remaining;
+        }
+    }
+
+    //prune_dead_ends
+    //Purpose:
+    //    Fills in corridors and doors which lead nowhere, until no dead ends are left.
+    fn prune_dead_ends(&mut self) {
+        let mut dead_ends = Vec::new();
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                dead_ends.push((i,j));
+            }
+        }
+        while let Some((row,col)) = dead_ends.pop() {
+            let tile = self.tile_matrix[row][col];
+            if (tile != DungeonTile::Corridor) && (tile != DungeonTile::Door) {
+                continue;
+            }
+            let exits: Vec<(usize,usize)> = self.get_neighbors(row, col).into_iter().filter(|&(i,j)| self.tile_matrix[i][j].is_floor()).collect();
+            if exits.len() <= 1 {
+                self.tile_matrix[row][col] = DungeonTile::Wall;
+                dead_ends.extend(exits); //the tile leading here may now be a dead end.
+            }
+        }
+    }
+
+    //label_regions
+    //Purpose:
+    //    Returns a matrix the size of the dungeon where every floor tile is labeled with the region it belongs to.
+    //Notes:
+    //  Walls are labeled with None, and labels are counted up from 0.
+    pub fn label_regions(&self) -> Vec<Vec<Option<usize>>> {
+        let mut label_matrix = vec![vec![None; self.columns]; self.rows];
+        let mut next_label = 0;
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                if self.tile_matrix[i][j].is_floor() && label_matrix[i][j].is_none() {
+                    let mut frontier = vec![(i,j)];
+                    label_matrix[i][j] = Some(next_label);
+                    while let Some((row,col)) = frontier.pop() {
+                        for (next_row,next_col) in self.get_neighbors(row, col) {
+                            if self.tile_matrix[next_row][next_col].is_floor() && label_matrix[next_row][next_col].is_none() {
+                                label_matrix[next_row][next_col] = Some(next_label);
+                                frontier.push((next_row,next_col));
+                            }
+                        }
+                    }
+                    next_label += 1;
+                }
+            }
+        }
+        label_matrix
+    }
+
+    fn is_next_to_door(&self, row: usize, col: usize) -> bool {
+        self.get_neighbors(row, col).iter().any(|&(i,j)| self.tile_matrix[i][j] == DungeonTile::Door)
+    }
+
+    //get_neighbors
+    //Purpose:
+    //    Returns the tiles sharing an edge with the tile (row, col).
+    pub fn get_neighbors(&self, row: usize, col: usize) -> Vec<(usize,usize)> {
+        let mut neighbors = Vec::new();
+        if row + 1 < self.rows {
+            neighbors.push((row+1,col));
+        }
+        if row > 0 {
+            neighbors.push((row-1,col));
+        }
+        if col + 1 < self.columns {
+            neighbors.push((row,col+1));
+        }
+        if col > 0 {
+            neighbors.push((row,col-1));
+        }
+        neighbors
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //get_region_count
+    //Purpose:
+    //    Returns how many separate regions of floor the dungeon has.
+    fn get_region_count(dungeon: &Dungeon) -> usize {
+        dungeon.label_regions().iter().flatten().filter_map(|label| *label).max().map_or(0, |label| label + 1)
+    }
+
+    #[test]
+    fn rooms_and_mazes_floor_is_connected() {
+        for &prune_dead_ends in [false, true].iter() {
+            let settings = DungeonSettings{prune_dead_ends, ..DungeonSettings::init()};
+            for _i in 0..10 {
+                let dungeon = Dungeon::init_rooms_and_mazes(31, 45, &settings).unwrap();
+                assert!(!dungeon.rooms.is_empty());
+                assert_eq!(get_region_count(&dungeon), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn pruned_rooms_and_mazes_have_no_dead_ends() {
+        for _i in 0..10 {
+            let dungeon = Dungeon::init_rooms_and_mazes(31, 45, &DungeonSettings::init()).unwrap();
+            for i in 0..dungeon.rows {
+                for j in 0..dungeon.columns {
+                    let tile = dungeon.tile_matrix[i][j];
+                    if (tile == DungeonTile::Corridor) || (tile == DungeonTile::Door) {
+                        let exits = dungeon.get_neighbors(i, j).into_iter().filter(|&(row,col)| dungeon.tile_matrix[row][col].is_floor()).count();
+                        assert!(exits >= 2, "dead end at ({}, {})", i, j);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hybrid_floor_is_connected_across_the_seam() {
+        //the cave floor and the maze dug around or inside it must form a single region.
+        let settings = DungeonSettings::init();
+        for &maze_on_floor in [false, true].iter() {
+            for _i in 0..10 {
+                let methods = [CaveMethod::CellularAutomaton(0.45, crate::cave::UpdateMode::Synchronous), CaveMethod::DrunkardsWalk(0.4)];
+                for method in methods.iter() {
+                    let dungeon = Dungeon::init_hybrid(31, 45, *method, &settings, maze_on_floor).unwrap();
+                    assert_eq!(get_region_count(&dungeon), 1, "{:?} with maze_on_floor {}", method, maze_on_floor);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bsp_rooms_are_joined_by_connections() {
+        for _i in 0..10 {
+            let dungeon = Dungeon::init_bsp(40, 60, &BspSettings::init()).unwrap();
+            assert!(dungeon.rooms.len() > 1);
+            //a tree joining every room has one connection fewer than it has rooms.
+            assert_eq!(dungeon.connections.len(), dungeon.rooms.len() - 1);
+            let mut reached = vec![false; dungeon.rooms.len()];
+            reached[0] = true;
+            let mut frontier = vec![0];
+            while let Some(room) = frontier.pop() {
+                for &(a,b) in dungeon.connections.iter() {
+                    let next = if a == room { b } else if b == room { a } else { continue };
+                    if !reached[next] {
+                        reached[next] = true;
+                        frontier.push(next);
+                    }
+                }
+            }
+            assert!(reached.iter().all(|&is_reached| is_reached));
+            assert_eq!(get_region_count(&dungeon), 1);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//Date Created: 10/18/2026
+//Purpose: To expose the maze and cave generators for use outside of the command line tool.
+
+extern crate rand;
+extern crate image;
+
+pub mod maze;
+pub mod cave;
+pub mod noise;
+pub mod dungeon;
+pub mod locks;
+pub mod placement;
+pub mod svg;
+pub mod tiled;
+pub mod animation;
+pub mod text;
+pub mod mesh;
+pub mod book;
+pub mod raster;
@@ -0,0 +1,281 @@
+//Date Created: 10/18/2026
+//Purpose To place locked doors and their keys in perfect mazes
+//Notes:
+//  Since a perfect maze has exactly one path between any two squares, its passages form a tree.
+//    Rooting the tree at the start, a square can be reached exactly when no locked door lies on its path to the root.
+
+use rand::Rng;
+use crate::maze::{Maze, Direction};
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum LockError {
+    Syntax(String),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LockError::Syntax(ref err_string) => write!(f,"{}",err_string),
+        }
+    }
+}
+
+impl Error for LockError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            LockError::Syntax(ref _err_string) => None,
+        }
+    }
+}
+
+////////////////////
+//Lock and key code
+////////////////////
+
+//LockedDoor
+//Purpose:
+//    To record a locked door, which blocks the passage leaving the square (row, col) in the direction dir.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockedDoor {
+    pub row: usize,
+    pub col: usize,
+    pub dir: Direction,
+}
+
+impl LockedDoor {
+
+    //blocks
+    //Purpose:
+    //    Returns true if the door is on the passage between the square (row, col) and its neighbor in the direction dir.
+    pub fn blocks(self, row: usize, col: usize, dir: Direction, neighbor: (usize,usize)) -> bool {
+        ((self.row, self.col) == (row, col) && self.dir == dir) || ((self.row, self.col) == neighbor && self.dir == dir.reverse())
+    }
+
+}
+
+//LockedMaze
+//Purpose:
+//    A perfect maze annotated with a start, a goal, locked doors and the keys which open them.
+//Notes:
+//  Key i opens door i, and the doors are ordered by when they are met on the way from the start to the goal.
+//  Squares are given as (row, column) in the same way as the maze matrix.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockedMaze {
+    pub maze: Maze,
+    pub start: (usize,usize),
+    pub goal: (usize,usize),
+    pub doors: Vec<LockedDoor>,
+    pub keys: Vec<(usize,usize)>,
+}
+
+//Parents records for each square the square before it on its path from the start,
+//  and the direction of the passage from that square, with None at the start.
+type Parents = Vec<Vec<Option<((usize,usize),Direction)>>>;
+
+impl LockedMaze {
+
+    //place_locks
+    //Purpose:
+    //    Places num_locks locked doors on the path from start to goal, and places the key to each door
+    //    in the part of the maze opened up by the door before it, so that the keys must be found in order.
+    //Pre-Conditions:
+    //    The maze is perfect, start and goal are different squares of the maze,
+    //    and the path between them is at least num_locks steps long.
+    pub fn place_locks(maze: Maze, start: (usize,usize), goal: (usize,usize), num_locks: usize) -> Result<LockedMaze,LockError> {
+        if (start.0 >= maze.rows) || (start.1 >= maze.columns) || (goal.0 >= maze.rows) || (goal.1 >= maze.columns) {
+			return Err(LockError::Syntax("The start and goal must be squares of the maze.".to_string()));
+        }
+        if start == goal {
+			return Err(LockError::Syntax("The start and goal must be different squares.".to_string()));
+        }
+        let parents = get_tree(&maze, start)?;
+        let path = get_path(&parents, goal);
+        if num_locks > path.len() - 1 {
+			return Err(LockError::Syntax(format!("The path from start to goal is only {} steps, too short for {} locks.", path.len() - 1, num_locks)));
+        }
+
+        let mut steps: Vec<usize> = (0..(path.len() - 1)).collect();
+        rand::thread_rng().shuffle(&mut steps);
+        steps.truncate(num_locks);
+        steps.sort();
+        let doors: Vec<LockedDoor> = steps.iter().map(|&step| {
+            //safe to unwrap since every square on the path after the start has a parent.
+            let (_parent, dir) = parents[path[step+1].0][path[step+1].1].unwrap();
+            LockedDoor{row: path[step].0, col: path[step].1, dir}
+        }).collect();
+
+        let mut locked_maze = LockedMaze{maze, start, goal, doors, keys: Vec::new()};
+        let mut reached = locked_maze.get_reachable(0);
+        for i in 0..num_locks {
+            //key i goes in the squares reached once door i-1 is open, which the doors before it did not reach.
+            let newly_reached = locked_maze.get_reachable(i);
+            let mut choices: Vec<(usize,usize)> = Vec::new();
+            for row in 0..locked_maze.maze.rows {
+                for col in 0..locked_maze.maze.columns {
+                    if newly_reached[row][col] && (i == 0 || !reached[row][col]) && (row,col) != start {
+                        choices.push((row,col));
+                    }
+                }
+            }
+            if choices.is_empty() { //only the start is reachable before the first door.
+                choices.push(start);
+            }
+            let choice = rand::thread_rng().gen_range(0, choices.len());
+            locked_maze.keys.push(choices[choice]);
+            reached = newly_reached;
+        }
+
+        if !locked_maze.check_locks() {
+			return Err(LockError::Syntax("The keys could not be placed before their doors.".to_string()));
+        }
+        Ok(locked_maze)
+    }
+
+    //check_locks
+    //Purpose:
+    //    Returns true if every key, and then the goal, can be reached using only the keys found before it.
+    //Notes:
+    //  This walks the spanning tree of the maze from each key and the goal back to the start,
+    //    and checks that every door on the way is opened by an earlier key.
+    //  Returns false if the maze is not perfect.
+    pub fn check_locks(&self) -> bool {
+        let parents = match get_tree(&self.maze, self.start) {
+            Ok(parents) => parents,
+            Err(_) => return false,
+        };
+        if self.keys.len() != self.doors.len() {
+            return false;
+        }
+        let mut targets = self.keys.clone();
+        targets.push(self.goal);
+        for (i, &target) in targets.iter().enumerate() {
+            let path = get_path(&parents, target);
+            for step in 0..(path.len() - 1) {
+                let (row, col) = path[step];
+                //safe to unwrap since every square on the path after the start has a parent.
+                let (_parent, dir) = parents[path[step+1].0][path[step+1].1].unwrap();
+                if let Some(door) = self.doors.iter().position(|door| door.blocks(row, col, dir, path[step+1])) {
+                    if door >= i {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    //get_solution
+    //Purpose:
+    //    Returns the squares on the path from the start to the goal, including both.
+    pub fn get_solution(&self) -> Vec<(usize,usize)> {
+        //safe to unwrap since the maze was checked to be perfect when the locks were placed.
+        let parents = get_tree(&self.maze, self.start).unwrap();
+        get_path(&parents, self.goal)
+    }
+
+    //get_reachable
+    //Purpose:
+    //    Returns a matrix the size of the maze which is true on the squares reachable from the start
+    //    when only the first num_open doors are open.
+    pub fn get_reachable(&self, num_open: usize) -> Vec<Vec<bool>> {
+        let mut reachable = vec![vec![false; self.maze.columns]; self.maze.rows];
+        reachable[self.start.0][self.start.1] = true;
+        let mut frontier = vec![self.start];
+        while let Some((row,col)) = frontier.pop() {
+            for (next_row,next_col,dir) in self.maze.get_open_neighbors(row, col) {
+                let locked = self.doors.iter().skip(num_open).any(|door| door.blocks(row, col, dir, (next_row,next_col)));
+                if !locked && !reachable[next_row][next_col] {
+                    reachable[next_row][next_col] = true;
+                    frontier.push((next_row,next_col));
+                }
+            }
+        }
+        reachable
+    }
+
+}
+
+//get_tree
+//Purpose:
+//    Returns the parent of every square in the spanning tree of the maze rooted at the start.
+//Notes:
+//  Returns an error if the maze is not perfect, that is if some square can't be reached or there is a loop.
+fn get_tree(maze: &Maze, start: (usize,usize)) -> Result<Parents,LockError> {
+    let mut parents: Parents = vec![vec![None; maze.columns]; maze.rows];
+    let mut visited = vec![vec![false; maze.columns]; maze.rows];
+    visited[start.0][start.1] = true;
+    let mut frontier = vec![start];
+    let mut num_visited = 1;
+    let mut num_passages = 0;
+    while let Some((row,col)) = frontier.pop() {
+        for (next_row,next_col,dir) in maze.get_open_neighbors(row, col) {
+            num_passages += 1;
+            if !visited[next_row][next_col] {
+                visited[next_row][next_col] = true;
+                parents[next_row][next_col] = Some(((row,col),dir));
+                num_visited += 1;
+                frontier.push((next_row,next_col));
+            }
+        }
+    }
+    //every passage is counted once from each end.
+    if (num_visited != maze.rows*maze.columns) || (num_passages != 2*(num_visited - 1)) {
+		return Err(LockError::Syntax("Locks can only be placed in a perfect maze.".to_string()));
+    }
+    Ok(parents)
+}
+
+//get_path
+//Purpose:
+//    Returns the squares on the path from the root of the tree to the target, including both.
+fn get_path(parents: &Parents, target: (usize,usize)) -> Vec<(usize,usize)> {
+    let mut path = vec![target];
+    let mut current = target;
+    while let Some((parent, _dir)) = parents[current.0][current.1] {
+        path.push(parent);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::GenerationType;
+
+    #[test]
+    fn keys_are_reachable_before_their_doors() {
+        for _i in 0..20 {
+            let maze = Maze::init_rect(8, 10, 0, GenerationType::Wilson).unwrap();
+            let locked_maze = LockedMaze::place_locks(maze, (0,0), (7,9), 4).unwrap();
+            assert!(locked_maze.check_locks());
+            assert_eq!(locked_maze.doors.len(), 4);
+            for (i, &(row, col)) in locked_maze.keys.iter().enumerate() {
+                assert!(locked_maze.get_reachable(i)[row][col], "key {} is behind its door", i);
+            }
+            //every door is on the way to the goal.
+            for i in 0..4 {
+                assert!(!locked_maze.get_reachable(i)[7][9]);
+            }
+            assert!(locked_maze.get_reachable(4)[7][9]);
+        }
+    }
+
+    #[test]
+    fn check_locks_finds_keys_behind_their_doors() {
+        let maze = Maze::init_rect(8, 10, 0, GenerationType::Prim).unwrap();
+        let mut locked_maze = LockedMaze::place_locks(maze, (0,0), (7,9), 3).unwrap();
+        locked_maze.keys[0] = locked_maze.goal;
+        assert!(!locked_maze.check_locks());
+    }
+}
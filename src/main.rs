@@ -4,18 +4,46 @@
 
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::env;
 use std::process;
 
-mod maze;
-use maze::Maze;
-use maze::Direction;
-use maze::GenerationType;
-
-mod cave;
-use cave::Cave;
+extern crate maze_generation;
+use maze_generation::maze::Maze;
+use maze_generation::maze::Direction;
+use maze_generation::maze::GenerationType;
+use maze_generation::maze::MazeWorld;
+use maze_generation::cave::Cave;
+use maze_generation::cave::UpdateMode;
+use maze_generation::cave::CaveMethod;
+use maze_generation::cave::MaterialSettings;
+use maze_generation::cave::Tile;
+use maze_generation::cave::CaveWorld;
+use maze_generation::cave::DEFULT_WALL_PROB;
+use maze_generation::cave;
+use maze_generation::noise::NoiseSettings;
+use maze_generation::dungeon::Dungeon;
+use maze_generation::dungeon::DungeonTile;
+use maze_generation::dungeon::DungeonSettings;
+use maze_generation::dungeon::BspSettings;
+use maze_generation::locks::LockedMaze;
+use maze_generation::placement;
+use maze_generation::placement::PlacementSettings;
+use maze_generation::svg;
+use maze_generation::svg::SvgSettings;
+use maze_generation::raster;
+use maze_generation::raster::RasterSettings;
+use maze_generation::tiled::TiledMap;
+use maze_generation::tiled::TilesetSettings;
+use maze_generation::animation;
+use maze_generation::animation::AnimationSettings;
+use maze_generation::text;
+use maze_generation::mesh::Mesh;
+use maze_generation::mesh::MeshSettings;
+use maze_generation::book;
+use maze_generation::book::Puzzle;
+use maze_generation::book::BookSettings;
 
 extern crate clap;
+extern crate rand;
 use clap::{Arg, App, ArgGroup};
 
 fn main() {
@@ -39,6 +67,76 @@ fn main() {
             .help("The program will generate a cave.")
             .short("c")
             .long("cave"))
+        .arg(Arg::with_name("drunkard")
+            .help("The cave will be dug by a drunkard's walk, requires the fraction of the cave which is floor, between 0 and 1.")
+            .takes_value(true)
+            .long("drunkard")
+            .requires("cave"))
+        .arg(Arg::with_name("aggregation")
+            .help("The cave will be grown by diffusion limited aggregation, requires the fraction of the cave which is floor, between 0 and 1.")
+            .takes_value(true)
+            .long("aggregation")
+            .requires("cave"))
+        .arg(Arg::with_name("noise")
+            .help("The cave will be shaped by noise, requires the size in cells of the largest features.")
+            .takes_value(true)
+            .long("noise")
+            .requires("cave"))
+        .group(
+            ArgGroup::with_name("Cave Method")
+                .args(&["drunkard","aggregation","noise"]))
+        .arg(Arg::with_name("octaves")
+            .help("Sets the number of octaves of noise, each adding finer detail, from 1 to 32. Default: 4.")
+            .takes_value(true)
+            .long("octaves")
+            .requires("noise"))
+        .arg(Arg::with_name("threshold")
+            .help("Sets the noise level between 0 and 1 at which the cave becomes wall. Default: 0.5.")
+            .takes_value(true)
+            .long("threshold")
+            .requires("noise"))
+        .arg(Arg::with_name("smooth")
+            .help("The noise is used as the starting state of the cellular automaton.")
+            .long("smooth")
+            .requires("noise"))
+        .arg(Arg::with_name("seed")
+            .help("Sets the seed of the noise, materials and chunks, so the same cave or maze can be made again. Default: random.")
+            .takes_value(true)
+            .long("seed"))
+        .arg(Arg::with_name("chunk")
+            .help("The cave or maze will be one chunk of an endless world, given by its x and y position in chunks,
+                   with x counting chunks east and y counting chunks north.
+                   Chunks made with the same seed and size line up seamlessly.")
+            .takes_value(true)
+            .number_of_values(2)
+            .allow_hyphen_values(true)
+            .long("chunk")
+            .conflicts_with_all(&["drunkard","aggregation","noise","mask","wrapping","asynchronous"]))
+        .arg(Arg::with_name("materials")
+            .help("The cave will have pools of water (~), lava (^) and veins of ore (*).")
+            .long("materials")
+            .requires("cave"))
+        .arg(Arg::with_name("asynchronous")
+            .help("Cave cells are updated in place, so each cell sees the neighbors already updated in the same iteration.")
+            .long("async")
+            .requires("cave")
+            .conflicts_with_all(&["drunkard","aggregation"]))
+        .arg(Arg::with_name("mask")
+            .help("Shapes the cave with a mask file, which is stretched to the size of the cave.
+                   A text mask uses '#' for wall, '.' for floor and '?' for random.
+                   An image mask uses black for wall, white for floor and grey for random.")
+            .takes_value(true)
+            .long("mask")
+            .requires("cave"))
+        .arg(Arg::with_name("cull")
+            .help("Regions of cave floor with fewer cells than the given number are filled in.")
+            .takes_value(true)
+            .long("cull")
+            .requires("cave"))
+        .arg(Arg::with_name("connect")
+            .help("Tunnels are dug so that every region of cave floor is connected to the largest one.")
+            .long("connect")
+            .requires("cave"))
         .arg(Arg::with_name("wilson")
             .help("The program will generate the maze with Wilson's Algoirthm")
             .short("w")
@@ -52,79 +150,655 @@ fn main() {
             .takes_value(true)
             .short("b")
             .long("backtrack"))
+        .arg(Arg::with_name("dungeon")
+            .help("The program will generate a dungeon of rooms (.) joined by corridors and doors (+).")
+            .short("d")
+            .long("dungeon")
+            .conflicts_with_all(&["chunk","wrapping"]))
+        .arg(Arg::with_name("rooms")
+            .help("The number of rooms the dungeon tries to place, rooms which would overlap are skipped. Default: 200.")
+            .takes_value(true)
+            .long("rooms")
+            .requires("dungeon"))
+        .arg(Arg::with_name("extra doors")
+            .help("The chance that each spare wall between two joined regions of the dungeon or hybrid level also gets a door. Default: 0.05.")
+            .takes_value(true)
+            .long("extra-doors"))
+        .arg(Arg::with_name("keep dead ends")
+            .help("The corridors of the dungeon or hybrid level keep their dead ends.")
+            .long("keep-dead-ends"))
+        .arg(Arg::with_name("hybrid")
+            .help("The cave is mixed with a maze into one level, the cave floor becomes caverns (.)
+                   and the rock between them a labyrinth of corridors joined to the caverns by doors (+).")
+            .long("hybrid")
+            .requires("cave")
+            .conflicts_with_all(&["chunk","mask","wrapping","cull","connect","materials"]))
+        .arg(Arg::with_name("maze on floor")
+            .help("The labyrinth of the hybrid level is carved inside the cave floor instead of the rock.")
+            .long("maze-on-floor")
+            .requires("hybrid"))
+        .arg(Arg::with_name("bsp")
+            .help("The program will generate a dungeon by binary space partition, with one room per leaf joined by corridors.")
+            .long("bsp")
+            .conflicts_with_all(&["chunk","wrapping"]))
+        .arg(Arg::with_name("leaf size")
+            .help("The smallest height or width in tiles of a leaf of the binary space partition, at least 3. Default: 8.")
+            .takes_value(true)
+            .long("leaf-size")
+            .requires("bsp"))
+        .arg(Arg::with_name("locks")
+            .help("Places the given number of locked doors (A, B, ...) on the path from the start (S) to the goal (G),
+                   with the key to each (a, b, ...) found before its door. At most 26.")
+            .takes_value(true)
+            .long("locks")
+            .conflicts_with_all(&["cave","dungeon","bsp","chunk"]))
+        .arg(Arg::with_name("items")
+            .help("Scatters items ($) reachable from the start (S) over the maze or cave,
+                   requires the smallest distance in squares allowed between two items.")
+            .takes_value(true)
+            .long("items")
+            .conflicts_with_all(&["dungeon","bsp","hybrid","locks"]))
+        .arg(Arg::with_name("item count")
+            .help("The most items which are placed. Default: 10.")
+            .takes_value(true)
+            .long("item-count")
+            .requires("items"))
+        .arg(Arg::with_name("dead end weight")
+            .help("How many times more likely items are to go in the dead ends of a maze. Default: 1.")
+            .takes_value(true)
+            .long("dead-end-weight")
+            .requires("items"))
+        .arg(Arg::with_name("open area weight")
+            .help("How strongly items favor cave floor far from walls, 0 for no preference. Default: 0.")
+            .takes_value(true)
+            .long("open-area-weight")
+            .requires("items"))
         .group(
             ArgGroup::with_name("Generation Method")
-                .args(&["wilson","prim","backtrack","cave"]))
+                .args(&["wilson","prim","backtrack","cave","dungeon","bsp"]))
         .arg(Arg::with_name("image")
             .help("The program will encode the maze as a png image instead of a text image.
                    Must also state the dimension of each square in pixels. Default: 10.")
             .takes_value(true)
             .short("i")
             .long("image"))
+        .arg(Arg::with_name("format")
+            .help("Sets how the output is written: as text, as an image, as an svg vector image, as json,
+                   as a Tiled map in xml (tmx) or json (tmj), as a gif animation of the generation,
+                   as a 3D mesh for printing in stl or obj, or as a pdf puzzle book of several mazes with their solutions.
+                   Tiled maps and gif animations are only written for mazes and caves, and meshes and puzzle books only for mazes.
+                   Json output needs the program to be built with the serde feature.
+                   Default: image if a block size is given, text otherwise.")
+            .takes_value(true)
+            .possible_values(&["text","image","svg","json","tmx","tmj","gif","stl","obj","pdf"])
+            .long("format"))
+        .arg(Arg::with_name("text style")
+            .help("Sets the characters used for text output: blocks of #, box drawing lines for mazes,
+                   or half blocks with two rows to a line for mazes and caves. Default: blocks.")
+            .takes_value(true)
+            .possible_values(&["blocks","box","half"])
+            .long("text-style"))
+        .arg(Arg::with_name("autotile")
+            .help("Walls in Tiled maps use one of 47 variants picked by their neighboring walls and corners, tiles 8 to 54 of the tileset.")
+            .long("autotile"))
+        .arg(Arg::with_name("tile ids")
+            .help("Sets the tiles of the tileset used in Tiled maps for floor, wall, water, lava and ore, counting from 0.
+                   Default: 0 1 2 3 4.")
+            .takes_value(true)
+            .number_of_values(5)
+            .long("tile-ids"))
+        .arg(Arg::with_name("frame skip")
+            .help("Sets how many steps of the generator pass between frames of a gif animation. Default: 1.")
+            .takes_value(true)
+            .long("frame-skip"))
+        .arg(Arg::with_name("frame delay")
+            .help("Sets how long each frame of a gif animation is shown, in hundredths of a second. Default: 5.")
+            .takes_value(true)
+            .long("frame-delay"))
+        .arg(Arg::with_name("wall thickness")
+            .help("Sets the thickness in millimeters of the walls of a maze mesh. Default: 2.")
+            .takes_value(true)
+            .long("wall-thickness"))
+        .arg(Arg::with_name("passage width")
+            .help("Sets the width in millimeters of the passages of a maze mesh. Default: 10.")
+            .takes_value(true)
+            .long("passage-width"))
+        .arg(Arg::with_name("wall height")
+            .help("Sets how far in millimeters the walls of a maze mesh rise above its base. Default: 10.")
+            .takes_value(true)
+            .long("wall-height"))
+        .arg(Arg::with_name("base thickness")
+            .help("Sets the thickness in millimeters of the base plate of a maze mesh. Default: 2.")
+            .takes_value(true)
+            .long("base-thickness"))
+        .arg(Arg::with_name("puzzles")
+            .help("Sets the number of mazes in a pdf puzzle book, each generated from the seed plus its position in the book. Default: 10.")
+            .takes_value(true)
+            .long("puzzles"))
+        .arg(Arg::with_name("per page")
+            .help("Sets the number of mazes on each page of a pdf puzzle book. Default: 1.")
+            .takes_value(true)
+            .long("per-page"))
+        .arg(Arg::with_name("solutions per page")
+            .help("Sets the number of solutions on each answer page at the back of a pdf puzzle book. Default: 4.")
+            .takes_value(true)
+            .long("solutions-per-page"))
+        .arg(Arg::with_name("stroke")
+            .help("Sets the width in pixels of maze walls in svg output. Default: a fifth of the block size.")
+            .takes_value(true)
+            .long("stroke"))
+        .arg(Arg::with_name("margin")
+            .help("Sets the empty space in pixels around svg output. Default: the block size.")
+            .takes_value(true)
+            .long("margin"))
+        .arg(Arg::with_name("image margin")
+            .help("Sets the empty space in pixels around maze images, which can be a fraction. Default: none.")
+            .takes_value(true)
+            .long("image-margin"))
+        .arg(Arg::with_name("wall px")
+            .help("Sets the width in pixels of the walls in maze images, which can be a fraction. Default: the block size.")
+            .takes_value(true)
+            .long("wall-px"))
+        .arg(Arg::with_name("passage px")
+            .help("Sets the width in pixels of the passages in maze images, which can be a fraction. Default: the block size.")
+            .takes_value(true)
+            .long("passage-px"))
+        .arg(Arg::with_name("antialias")
+            .help("Maze images blend the colors of pixels which are only partly covered by a wall.")
+            .long("antialias"))
+        .arg(Arg::with_name("wall color")
+            .help("Sets the color of walls in image output, as a hex code such as 000000. Default: black.")
+            .takes_value(true)
+            .long("wall-color"))
+        .arg(Arg::with_name("floor color")
+            .help("Sets the color of passages and cave floor in image output, as a hex code such as ffffff. Default: white.")
+            .takes_value(true)
+            .long("floor-color")
+            .alias("passage-color"))
+        .arg(Arg::with_name("background color")
+            .help("Sets the color of the margin around maze images, as a hex code such as ffffff. Default: the floor color.")
+            .takes_value(true)
+            .long("background-color"))
         .arg(Arg::with_name("wrapping")
-            .help("The maze is allowed to pass outside the grid by wrapping to the other side.
-                   Use once for a maze on a tube, and twice for a maze on a torus.")
+            .help("The maze or cave is allowed to pass outside the grid by wrapping to the other side.
+                   Use once for a tube, and twice for a torus.")
             .takes_value(false)
             .long("wrap")
             .multiple(true))
         .get_matches();
 
-    print!("Using the following arguments to generate from:\n");
-    print!("x: {}    y: {}    Caves: {}    Prim: {}    Wilson: {}    Wrapping {}\n",
-        matches.value_of("maze x_length").unwrap().to_string(),
-        matches.value_of("maze y_length").unwrap().to_string(),
+    println!("Using the following arguments to generate from:");
+    println!("x: {}    y: {}    Caves: {}    Prim: {}    Wilson: {}    Wrapping {}",
+        matches.value_of("maze x_length").unwrap(),
+        matches.value_of("maze y_length").unwrap(),
         matches.is_present("cave"),
         matches.is_present("wilson"),
         matches.is_present("prim"),
         matches.is_present("wrapping"),
     );
-    print!("output file: {}\n", matches.value_of("output_file").unwrap().to_string());
+    println!("output file: {}", matches.value_of("output_file").unwrap());
 
     //safe to unwrap since the arugment is required.
     let output_file_name: String = matches.value_of("output_file").unwrap().to_string();
     let rows = matches.value_of("maze x_length").unwrap().parse::<usize>().unwrap();
     let columns = matches.value_of("maze y_length").unwrap().parse::<usize>().unwrap();
     let wrap = matches.occurrences_of("wrapping");
+    let floor_color = get_color_arg(&matches, "floor color", image::Rgb([255, 255, 255]));
+    let colors = ImageColors{
+        wall: get_color_arg(&matches, "wall color", image::Rgb([0, 0, 0])),
+        floor: floor_color,
+        background: get_color_arg(&matches, "background color", floor_color),
+        water: image::Rgb([48, 96, 224]),
+        lava: image::Rgb([224, 72, 16]),
+        ore: image::Rgb([212, 175, 55]),
+        corridor: image::Rgb([200, 200, 200]),
+        door: image::Rgb([150, 90, 40]),
+        start: image::Rgb([0, 160, 0]),
+        goal: image::Rgb([200, 0, 0]),
+        item: image::Rgb([200, 0, 200]),
+    };
+
+    let picture_format = get_picture_format(&matches, &colors);
+    let json_output = matches.value_of("format") == Some("json");
+    let tiled_format = matches.value_of("format").filter(|format| *format == "tmx" || *format == "tmj");
+    if tiled_format.is_some() && (matches.is_present("dungeon") || matches.is_present("bsp") || matches.is_present("hybrid") || matches.is_present("locks")) {
+        print!("Tiled maps can only be written for mazes and caves.\nExiting.");
+        process::exit(1);
+    }
+    let tileset = get_tileset_settings(&matches);
+    let text_style = matches.value_of("text style").unwrap_or("blocks");
+    if text_style != "blocks" && ["dungeon","bsp","hybrid","locks","items"].iter().any(|arg| matches.is_present(arg)) {
+        print!("The box and half text styles can only be used for mazes and caves.\nExiting.");
+        process::exit(1);
+    }
+    if text_style == "box" && matches.is_present("cave") {
+        print!("The box text style can only be used for mazes, use half for caves.\nExiting.");
+        process::exit(1);
+    }
+    let mesh_format = matches.value_of("format").filter(|format| *format == "stl" || *format == "obj");
+    if mesh_format.is_some() && ["cave","dungeon","bsp","hybrid","locks","items"].iter().any(|arg| matches.is_present(arg)) {
+        print!("Meshes can only be made of mazes.\nExiting.");
+        process::exit(1);
+    }
+    let book_output = matches.value_of("format") == Some("pdf");
+    if book_output && ["cave","dungeon","bsp","hybrid","locks","items","chunk","wrapping"].iter().any(|arg| matches.is_present(arg)) {
+        print!("Puzzle books can only be made of mazes which don't wrap.\nExiting.");
+        process::exit(1);
+    }
+    let raster_args = ["wall px","passage px","image margin","antialias"];
+    let maze_image = match picture_format {
+        Some(PictureFormat::Image(_)) => !["cave","dungeon","bsp","hybrid","locks","items"].iter().any(|arg| matches.is_present(arg)),
+        _ => false,
+    };
+    if !maze_image && raster_args.iter().any(|arg| matches.is_present(arg)) {
+        print!("Wall and passage widths, image margins and anti-aliasing can only be used for images of mazes.\nExiting.");
+        process::exit(1);
+    }
+    let svg_output = matches!(picture_format, Some(PictureFormat::Svg(_)));
+    if !svg_output && (matches.is_present("stroke") || matches.is_present("margin")) {
+        print!("The stroke and margin can only be set for svg output.\nExiting.");
+        process::exit(1);
+    }
+    let gif_output = matches.value_of("format") == Some("gif");
+    let post_processing = ["dungeon","bsp","hybrid","locks","items","chunk","cull","connect","materials"];
+    if gif_output && post_processing.iter().any(|arg| matches.is_present(arg)) {
+        print!("Gif animations can only be recorded for mazes and caves which are not changed after they are generated.\nExiting.");
+        process::exit(1);
+    }
+
+    let seed = match matches.value_of("seed") {
+        Some(seed) => seed.parse::<u64>().unwrap(),
+        None => rand::random::<u64>(),
+    };
+    if matches.is_present("noise") || matches.is_present("materials") || matches.is_present("chunk") || book_output {
+        println!("seed: {}", seed);
+    }
+    let chunk_position: Option<Vec<i64>> = matches.values_of("chunk").map(|position| position.map(|value| value.parse::<i64>().unwrap()).collect());
 
     if matches.is_present("cave"){
-        let my_cave = Cave::init_cave(rows,columns).unwrap();
-        match matches.value_of("image") {
-            Some(block_size) => {
-              let mut block = block_size.parse::<usize>().unwrap();
-              if block < 10 {
-                block = 10
-              }
-              print_picture_cave(&my_cave, output_file_name, block)
+        let update_mode = if matches.is_present("asynchronous") {
+            UpdateMode::Asynchronous
+        } else {
+            UpdateMode::Synchronous
+        };
+        let cave_method = match (matches.value_of("drunkard"), matches.value_of("aggregation"), matches.value_of("noise")) {
+            (Some(floor_ratio),_,_) => CaveMethod::DrunkardsWalk(floor_ratio.parse::<f64>().unwrap()),
+            (_,Some(floor_ratio),_) => CaveMethod::Aggregation(floor_ratio.parse::<f64>().unwrap()),
+            (_,_,Some(scale)) => {
+                let mut settings = NoiseSettings::init(scale.parse::<f64>().unwrap(), seed);
+                if let Some(octaves) = matches.value_of("octaves") {
+                    settings.octaves = octaves.parse::<usize>().unwrap();
+                }
+                if let Some(threshold) = matches.value_of("threshold") {
+                    settings.threshold = threshold.parse::<f64>().unwrap();
+                }
+                if matches.is_present("smooth") {
+                    CaveMethod::Noise(settings,Some(update_mode))
+                } else {
+                    CaveMethod::Noise(settings,None)
+                }
+            },
+            _ => CaveMethod::CellularAutomaton(DEFULT_WALL_PROB,update_mode),
+        };
+        if matches.is_present("hybrid") {
+            let my_dungeon = match Dungeon::init_hybrid(rows,columns,cave_method,&get_dungeon_settings(&matches),matches.is_present("maze on floor")) {
+                Ok(my_dungeon) => my_dungeon,
+                Err(err) => {
+                    print!("Unable to make the hybrid level: {}\nExiting.", err);
+                    process::exit(1);
+                },
+            };
+            if json_output {
+                print_json(&my_dungeon, output_file_name);
+                return;
+            }
+            match picture_format {
+                Some(picture_format) => print_picture(&get_dungeon_colors(&my_dungeon, &colors), output_file_name, &picture_format),
+                None => print_dungeon(&my_dungeon, output_file_name),
+            }
+            return;
+        }
+        let condition_grid = match matches.value_of("mask") {
+            Some(mask_file_name) => {
+                match cave::read_condition_grid(mask_file_name).and_then(|mask| cave::scale_condition_grid(&mask, rows, columns)) {
+                    Ok(condition_grid) => condition_grid,
+                    Err(err) => {
+                        print!("Unable to read the mask {}: {}\nExiting.", mask_file_name, err);
+                        process::exit(1);
+                    },
+                }
             },
+            None => vec![vec![1; columns]; rows],
+        };
+        if gif_output {
+            let gif_file = create_gif_file(output_file_name);
+            if let Err(err) = animation::record_cave(rows,columns,wrap as usize,condition_grid,cave_method,&get_animation_settings(&matches,&colors),gif_file) {
+                print!("Unable to record the cave: {}\nExiting.", err);
+                process::exit(1);
+            }
+            return;
+        }
+        let mut my_cave = match chunk_position {
+            Some(chunk_position) => CaveWorld::init(rows,columns,seed).unwrap().get_chunk(chunk_position[0],chunk_position[1]),
+            None => match Cave::init_cave_with_method(rows,columns,wrap as usize,condition_grid.clone(),cave_method) {
+                Ok(my_cave) => my_cave,
+                Err(err) => {
+                    print!("Unable to make the cave: {}\nExiting.", err);
+                    process::exit(1);
+                },
+            },
+        };
+        if let Some(min_size) = matches.value_of("cull") {
+            my_cave.cull_regions(min_size.parse::<usize>().unwrap());
+        }
+        if matches.is_present("connect") {
+            my_cave.join_regions(&condition_grid);
+        }
+        if matches.is_present("materials") {
+            my_cave.place_materials(&MaterialSettings::init(seed));
+        }
+        if matches.is_present("items") {
+            //the start is the first cell of the largest region of floor.
+            let start = match my_cave.get_regions().first() {
+                Some(region) => region[0],
+                None => {
+                    print!("The cave has no floor to place items on.\nExiting.");
+                    process::exit(1);
+                },
+            };
+            let items = match placement::place_in_cave(&my_cave, start, &get_placement_settings(&matches)) {
+                Ok(items) => items,
+                Err(err) => {
+                    print!("Unable to place the items: {}\nExiting.", err);
+                    process::exit(1);
+                },
+            };
+            let mut char_grid: Vec<Vec<char>> = my_cave.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|tile| tile.glyph()).collect()).collect();
+            for &(row, col) in items.iter() {
+                char_grid[row][col] = '$';
+            }
+            char_grid[start.0][start.1] = 'S';
+            if let Some(tiled_format) = tiled_format {
+                print_tiled(&get_cave_map(&my_cave, &tileset, &items), output_file_name, tiled_format);
+                return;
+            }
+            if json_output {
+                print_json(&PlacedItems{level: &my_cave, start, items}, output_file_name);
+                return;
+            }
+            match picture_format {
+                Some(picture_format) => print_picture(&get_char_colors(&char_grid, &colors), output_file_name, &picture_format),
+                None => print_char_grid(&char_grid, output_file_name),
+            }
+            return;
+        }
+        if let Some(tiled_format) = tiled_format {
+            print_tiled(&get_cave_map(&my_cave, &tileset, &[]), output_file_name, tiled_format);
+            return;
+        }
+        if json_output {
+            print_json(&my_cave, output_file_name);
+            return;
+        }
+        match picture_format {
+            Some(PictureFormat::Svg(settings)) => save_svg(&svg::cave_to_svg(&my_cave, &settings), output_file_name),
+            Some(picture_format) => print_picture(&get_cave_colors(&my_cave, &colors), output_file_name, &picture_format),
+            None if text_style == "half" => save_text(&text::cave_to_half_blocks(&my_cave), output_file_name),
             None => print_cave(&my_cave,output_file_name),
         }
+    } else if matches.is_present("dungeon") || matches.is_present("bsp") {
+        let dungeon_result = if matches.is_present("bsp") {
+            let mut settings = BspSettings::init();
+            if let Some(leaf_size) = matches.value_of("leaf size") {
+                settings.min_leaf_size = leaf_size.parse::<usize>().unwrap();
+            }
+            Dungeon::init_bsp(rows,columns,&settings)
+        } else {
+            Dungeon::init_rooms_and_mazes(rows,columns,&get_dungeon_settings(&matches))
+        };
+        let my_dungeon = match dungeon_result {
+            Ok(my_dungeon) => my_dungeon,
+            Err(err) => {
+                print!("Unable to make the dungeon: {}\nExiting.", err);
+                process::exit(1);
+            },
+        };
+        if json_output {
+            print_json(&my_dungeon, output_file_name);
+            return;
+        }
+        match picture_format {
+            Some(picture_format) => print_picture(&get_dungeon_colors(&my_dungeon, &colors), output_file_name, &picture_format),
+            None => print_dungeon(&my_dungeon, output_file_name),
+        }
     } else { //we generate a maze
         let (wilson, prim, backtrack) = (matches.is_present("wilson"),matches.is_present("prim"),matches.is_present("backtrack"));
-        print!("{} {} {} {}\n", wilson, prim, backtrack, wilson || prim || backtrack);
         // sanity check before generation
         if !(wilson || prim || backtrack) {
           print!("You must select (w)ilson, (p)rim or (b)acktrack when not running (c)aves. \nExiting.");
           process::exit(1);
         }
-        let my_maze;
-        match (wilson, prim, backtrack){
-            (true,_,_) => my_maze = Maze::init_rect(rows, columns, wrap as usize,GenerationType::Wilson).unwrap(),
-            (_,true,_) => my_maze = Maze::init_rect(rows, columns, wrap as usize,GenerationType::Prim).unwrap(),
-            (_,_,true) => my_maze = Maze::init_rect(rows,columns,wrap as usize,GenerationType::Backtrack(matches.value_of("backtrack").unwrap().parse::<f64>().unwrap())).unwrap(),
+        let method = match (wilson, prim, backtrack){
+            (true,_,_) => GenerationType::Wilson,
+            (_,true,_) => GenerationType::Prim,
+            (_,_,true) => GenerationType::Backtrack(matches.value_of("backtrack").unwrap().parse::<f64>().unwrap()),
             _ => unreachable!(),
+        };
+        if gif_output {
+            let gif_file = create_gif_file(output_file_name);
+            if let Err(err) = animation::record_maze(rows,columns,wrap as usize,method,&get_animation_settings(&matches,&colors),gif_file) {
+                print!("Unable to record the maze: {}\nExiting.", err);
+                process::exit(1);
+            }
+            return;
+        }
+        if book_output {
+            print_book(rows, columns, method, seed, &matches, output_file_name);
+            return;
         }
+        let my_maze = match chunk_position {
+            Some(chunk_position) => MazeWorld::init(rows,columns,method,seed).unwrap().get_chunk(chunk_position[0],chunk_position[1]),
+            None => Maze::init_rect(rows,columns,wrap as usize,method).unwrap(),
+        };
 
-        match matches.value_of("image") {
-            Some(block_size) => {
-              let mut block = block_size.parse::<usize>().unwrap();
-              if block < 10 {
-                block = 10
-              }
-              print_picture_maze(&my_maze, output_file_name, block)
-            },
-            None => print_maze(&my_maze, output_file_name),
+        if matches.is_present("items") {
+            //the start is the bottom left square.
+            let start = (0,0);
+            let items = match placement::place_in_maze(&my_maze, start, &get_placement_settings(&matches)) {
+                Ok(items) => items,
+                Err(err) => {
+                    print!("Unable to place the items: {}\nExiting.", err);
+                    process::exit(1);
+                },
+            };
+            let mut char_grid: Vec<Vec<char>> = my_maze.get_wall_grid().iter().map(|wall_row| {
+                wall_row.iter().map(|&is_wall| if is_wall { '#' } else { ' ' }).collect()
+            }).collect();
+            for &(row, col) in items.iter() {
+                let (grid_row, grid_col) = my_maze.get_grid_position(row, col);
+                char_grid[grid_row][grid_col] = '$';
+            }
+            let (start_row, start_col) = my_maze.get_grid_position(start.0, start.1);
+            char_grid[start_row][start_col] = 'S';
+            if let Some(tiled_format) = tiled_format {
+                print_tiled(&get_maze_map(&my_maze, &tileset, &items), output_file_name, tiled_format);
+                return;
+            }
+            if json_output {
+                print_json(&PlacedItems{level: &my_maze, start, items}, output_file_name);
+                return;
+            }
+            match picture_format {
+                Some(picture_format) => print_picture(&get_char_colors(&char_grid, &colors), output_file_name, &picture_format),
+                None => print_char_grid(&char_grid, output_file_name),
+            }
+            return;
+        }
+        if let Some(num_locks) = matches.value_of("locks") {
+            let num_locks = num_locks.parse::<usize>().unwrap();
+            if num_locks > 26 {
+                print!("At most 26 locks can be placed.\nExiting.");
+                process::exit(1);
+            }
+            //the start is the bottom left square and the goal the top right square.
+            let locked_maze = match LockedMaze::place_locks(my_maze, (0,0), (rows-1,columns-1), num_locks) {
+                Ok(locked_maze) => locked_maze,
+                Err(err) => {
+                    print!("Unable to place the locks: {}\nExiting.", err);
+                    process::exit(1);
+                },
+            };
+            if json_output {
+                print_json(&locked_maze, output_file_name);
+                return;
+            }
+            match picture_format {
+                Some(picture_format) => print_picture(&get_char_colors(&get_lock_grid(&locked_maze), &colors), output_file_name, &picture_format),
+                None => print_char_grid(&get_lock_grid(&locked_maze), output_file_name),
+            }
+            return;
+        }
+
+        if let Some(tiled_format) = tiled_format {
+            print_tiled(&get_maze_map(&my_maze, &tileset, &[]), output_file_name, tiled_format);
+            return;
         }
+        if let Some(mesh_format) = mesh_format {
+            print_mesh(&my_maze, output_file_name, mesh_format, &get_mesh_settings(&matches));
+            return;
+        }
+        if json_output {
+            print_json(&my_maze, output_file_name);
+            return;
+        }
+        match picture_format {
+            Some(PictureFormat::Image(block)) => print_picture_maze(&my_maze, output_file_name, &get_raster_settings(&matches, block, &colors)),
+            Some(PictureFormat::Svg(settings)) => save_svg(&svg::maze_to_svg(&my_maze, &settings), output_file_name),
+            None if text_style == "box" => save_text(&text::maze_to_box_text(&my_maze), output_file_name),
+            None if text_style == "half" => save_text(&text::grid_to_half_blocks(&my_maze.get_wall_grid()), output_file_name),
+            None => save_text(&text::maze_to_text(&my_maze), output_file_name),
+        }
+    }
+}
+
+//ImageColors
+//Purpose:
+//    The colors used when writing a maze or cave as an image.
+struct ImageColors {
+    wall: image::Rgb<u8>,
+    floor: image::Rgb<u8>,
+    background: image::Rgb<u8>,
+    water: image::Rgb<u8>,
+    lava: image::Rgb<u8>,
+    ore: image::Rgb<u8>,
+    corridor: image::Rgb<u8>,
+    door: image::Rgb<u8>,
+    start: image::Rgb<u8>,
+    goal: image::Rgb<u8>,
+    item: image::Rgb<u8>,
+}
+
+impl ImageColors {
+
+    fn get_tile_color(&self, tile: Tile) -> image::Rgb<u8> {
+        match tile {
+            Tile::Floor => self.floor,
+            Tile::Wall => self.wall,
+            Tile::Water => self.water,
+            Tile::Lava => self.lava,
+            Tile::Ore => self.ore,
+        }
+    }
+
+    fn get_dungeon_tile_color(&self, tile: DungeonTile) -> image::Rgb<u8> {
+        match tile {
+            DungeonTile::Wall => self.wall,
+            DungeonTile::Room => self.floor,
+            DungeonTile::Corridor => self.corridor,
+            DungeonTile::Door => self.door,
+        }
+    }
+
+}
+
+//get_dungeon_settings
+//Purpose:
+//    Reads the settings of a rooms and mazes dungeon or a hybrid level from the command line.
+fn get_dungeon_settings(matches: &clap::ArgMatches) -> DungeonSettings {
+    let mut settings = DungeonSettings::init();
+    if let Some(room_attempts) = matches.value_of("rooms") {
+        settings.room_attempts = room_attempts.parse::<usize>().unwrap();
+    }
+    if let Some(chance) = matches.value_of("extra doors") {
+        settings.extra_connection_chance = chance.parse::<f64>().unwrap();
     }
+    settings.prune_dead_ends = !matches.is_present("keep dead ends");
+    settings
+}
+
+//get_placement_settings
+//Purpose:
+//    Reads the settings for scattering items from the command line.
+fn get_placement_settings(matches: &clap::ArgMatches) -> PlacementSettings {
+    //safe to unwrap since the settings are only read when items are asked for.
+    let min_distance = matches.value_of("items").unwrap().parse::<f64>().unwrap();
+    let max_count = match matches.value_of("item count") {
+        Some(count) => count.parse::<usize>().unwrap(),
+        None => 10,
+    };
+    let mut settings = PlacementSettings::init(min_distance, max_count);
+    if let Some(weight) = matches.value_of("dead end weight") {
+        settings.dead_end_weight = weight.parse::<f64>().unwrap();
+    }
+    if let Some(weight) = matches.value_of("open area weight") {
+        settings.open_area_weight = weight.parse::<f64>().unwrap();
+    }
+    settings
+}
+
+//get_color_arg
+//Purpose:
+//    Reads a color given as a six digit hex code from the command line, using the default if it is not given.
+fn get_color_arg(matches: &clap::ArgMatches, arg_name: &str, default: image::Rgb<u8>) -> image::Rgb<u8> {
+    match matches.value_of(arg_name) {
+        Some(hex_code) => {
+            match parse_color(hex_code) {
+                Some(color) => color,
+                None => {
+                    print!("{} is not a color, expected a hex code such as ff8000.\nExiting.", hex_code);
+                    process::exit(1);
+                },
+            }
+        },
+        None => default,
+    }
+}
+
+fn parse_color(hex_code: &str) -> Option<image::Rgb<u8>> {
+    let hex_code = hex_code.trim_start_matches('#');
+    if hex_code.len() != 6 || !hex_code.is_ascii() {
+        return None;
+    }
+    let mut channels = [0; 3];
+    for i in 0..3 {
+        channels[i] = u8::from_str_radix(&hex_code[2*i..2*i+2], 16).ok()?;
+    }
+    Some(image::Rgb(channels))
+}
+
+//get_pixels_arg
+//Purpose:
+//    Reads a size in pixels from the command line, exiting if it isn't a number.
+fn get_pixels_arg(matches: &clap::ArgMatches, arg_name: &str) -> Option<f64> {
+    matches.value_of(arg_name).map(|value| {
+        match value.parse::<f64>() {
+            Ok(pixels) => pixels,
+            Err(_) => {
+                print!("{} is not a number of pixels for the {}.\nExiting.", value, arg_name);
+                process::exit(1);
+            },
+        }
+    })
 }
 
 fn print_cave(my_cave: &Cave, output_file_name: String){
@@ -132,116 +806,432 @@ fn print_cave(my_cave: &Cave, output_file_name: String){
     let mut f = BufWriter::new(file);
     for i in 0..my_cave.rows {
         for j in 0..my_cave.columns {
-            if my_cave.cave_matrix[i][j] { //if there is a wall
-                f.write("#".as_bytes()).unwrap();
-            }else{
-                f.write(" ".as_bytes()).unwrap();
-            }
+            write!(f, "{}", my_cave.tile_matrix[i][j].glyph()).unwrap();
         }
-        f.write("\n".as_bytes()).unwrap();
+        f.write_all("\n".as_bytes()).unwrap();
     }
 }
 
-fn print_picture_cave(my_cave: &Cave, output_file_name: String, block_size: usize){
-    let block_size_u32 = block_size as u32;
-    let mut imgbuf = image::ImageBuffer::new((block_size*my_cave.columns) as u32, (block_size*my_cave.rows) as u32);
-    for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
-        if my_cave.cave_matrix[(x/block_size_u32) as usize][(y/block_size_u32) as usize] == true {
-            *pixel = image::Rgb([0, 0, 0]);
-        }else {
-            *pixel = image::Rgb([255, 255, 255]);
+fn get_cave_colors(my_cave: &Cave, colors: &ImageColors) -> Vec<Vec<image::Rgb<u8>>> {
+    my_cave.tile_matrix.iter().map(|tile_row| {
+        tile_row.iter().map(|&tile| colors.get_tile_color(tile)).collect()
+    }).collect()
+}
+
+fn print_dungeon(my_dungeon: &Dungeon, output_file_name: String){
+    let file = File::create(output_file_name).expect("Unable to create file");
+    let mut f = BufWriter::new(file);
+    for i in 0..my_dungeon.rows {
+        for j in 0..my_dungeon.columns {
+            write!(f, "{}", my_dungeon.tile_matrix[i][j].glyph()).unwrap();
         }
+        f.write_all("\n".as_bytes()).unwrap();
     }
+}
 
-    if output_file_name.contains(".jpeg") || output_file_name.contains(".png") {
-        imgbuf.save(output_file_name).unwrap();
-    } else {
-      imgbuf.save(output_file_name + ".png").unwrap();
-    }
+fn get_dungeon_colors(my_dungeon: &Dungeon, colors: &ImageColors) -> Vec<Vec<image::Rgb<u8>>> {
+    my_dungeon.tile_matrix.iter().map(|tile_row| {
+        tile_row.iter().map(|&tile| colors.get_dungeon_tile_color(tile)).collect()
+    }).collect()
+}
+
+//PlacedItems
+//Purpose:
+//    A level along with the items scattered over it, for writing as json.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+struct PlacedItems<'a, T> {
+    level: &'a T,
+    start: (usize,usize),
+    items: Vec<(usize,usize)>,
 }
 
-fn print_maze(my_maze: &Maze, output_file_name: String){
+//print_json
+//Purpose:
+//    Writes a level as json.
+#[cfg(feature = "serde")]
+fn print_json<T: serde::Serialize>(level: &T, output_file_name: String){
     let file = File::create(output_file_name).expect("Unable to create file");
     let mut f = BufWriter::new(file);
-    f.write("#".as_bytes()).unwrap();
-    for i in 0..my_maze.columns {
-        if my_maze.maze_matrix[my_maze.rows-1][i].has_dir(Direction::North) == true {
-            f.write(" ".as_bytes()).unwrap();
-        }else{
-            f.write("#".as_bytes()).unwrap();
-        }
-        f.write("#".as_bytes()).unwrap();
-    }
-    f.write("\n".as_bytes()).unwrap();
-    for i in (0..my_maze.rows).rev() {
-        if my_maze.maze_matrix[i][0].has_dir(Direction::West) == true {
-            f.write(" ".as_bytes()).unwrap();
-        }else{
-            f.write("#".as_bytes()).unwrap();
-        }
-        for j in 0..my_maze.columns{
-            f.write(" ".as_bytes()).unwrap();
-            if my_maze.maze_matrix[i][j].has_dir(Direction::East) == true {
-            f.write(" ".as_bytes()).unwrap();
-            }else{
-                f.write("#".as_bytes()).unwrap();
-            }
-        }
-        f.write("\n".as_bytes()).unwrap();
-        f.write("#".as_bytes()).unwrap();
-        for j in 0..my_maze.columns {
-            if my_maze.maze_matrix[i][j].has_dir(Direction::South) == true {
-                f.write(" ".as_bytes()).unwrap();
-            }else{
-                f.write("#".as_bytes()).unwrap();
-            }
-            f.write("#".as_bytes()).unwrap();
-        }
-        f.write("\n".as_bytes()).unwrap();
+    serde_json::to_writer_pretty(&mut f, level).unwrap();
+    f.write_all("\n".as_bytes()).unwrap();
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json<T>(_level: &T, _output_file_name: String){
+    print!("Json output needs the program to be built with the serde feature.\nExiting.");
+    process::exit(1);
+}
+
+//get_tileset_settings
+//Purpose:
+//    Reads the tileset used for Tiled maps from the command line.
+fn get_tileset_settings(matches: &clap::ArgMatches) -> TilesetSettings {
+    let mut tileset = TilesetSettings::init();
+    if let Some(tile_ids) = matches.values_of("tile ids") {
+        let tile_ids: Vec<u32> = tile_ids.map(|id| id.parse::<u32>().unwrap()).collect();
+        tileset.floor = tile_ids[0];
+        tileset.wall = tile_ids[1];
+        tileset.water = tile_ids[2];
+        tileset.lava = tile_ids[3];
+        tileset.ore = tile_ids[4];
     }
-    f.flush().unwrap();
+    if matches.is_present("autotile") {
+        tileset = tileset.with_autotiling(8);
+    }
+    tileset
 }
 
-fn print_picture_maze(my_maze: &Maze, output_file_name: String, block_size: usize){
-    let block_size_u32 = block_size as u32;
-    let mut imgbuf = image::ImageBuffer::new((block_size*(2*my_maze.columns + 1)) as u32, (block_size*(2*my_maze.rows + 1)) as u32);
-    let mut wall_matrix = vec![vec![0; 2*my_maze.rows+1]; 2*my_maze.columns+1];
-    wall_matrix[0][2*my_maze.rows] = 1;
-    for i in 0..my_maze.columns {
-        if my_maze.maze_matrix[my_maze.rows-1][i].has_dir(Direction::North) == false {
-            wall_matrix[2*i+1][2*my_maze.rows] = 1;
-        }
-        wall_matrix[2*i+2][2*my_maze.rows] = 1;
+//get_maze_map
+//Purpose:
+//    Returns a Tiled map of the maze, with the start at the bottom left square, the exit at the top right square, and the items.
+fn get_maze_map(my_maze: &Maze, tileset: &TilesetSettings, items: &[(usize,usize)]) -> TiledMap {
+    let mut map = TiledMap::from_maze(my_maze, tileset);
+    let (start_row, start_col) = my_maze.get_grid_position(0, 0);
+    map.add_object("start", "start", start_row, start_col);
+    let (exit_row, exit_col) = my_maze.get_grid_position(my_maze.rows - 1, my_maze.columns - 1);
+    map.add_object("exit", "exit", exit_row, exit_col);
+    for (i, &(row, col)) in items.iter().enumerate() {
+        let (item_row, item_col) = my_maze.get_grid_position(row, col);
+        map.add_object(&format!("item {}", i + 1), "item", item_row, item_col);
     }
-    for i in (0..my_maze.rows).rev() {
-        if my_maze.maze_matrix[i][0].has_dir(Direction::West) == false {
-            wall_matrix[0][2*my_maze.rows-1-2*i] = 1;
-        }
-        for j in 0..my_maze.columns{
-            if my_maze.maze_matrix[i][j].has_dir(Direction::East) == false {
-                wall_matrix[2*j+2][2*my_maze.rows-1-2*i] = 1;
+    map
+}
+
+//get_cave_map
+//Purpose:
+//    Returns a Tiled map of the cave, with the start at the first cell of the largest region of floor,
+//    the exit at the cell of that region farthest from the start, and the items.
+fn get_cave_map(my_cave: &Cave, tileset: &TilesetSettings, items: &[(usize,usize)]) -> TiledMap {
+    let mut map = TiledMap::from_cave(my_cave, tileset);
+    if let Some(region) = my_cave.get_regions().first() {
+        let start = region[0];
+        let distances = my_cave.get_distances(start);
+        //safe to unwrap since every cell of the region is reachable from the start.
+        let exit = *region.iter().max_by_key(|&&(row, col)| distances[row][col].unwrap()).unwrap();
+        map.add_object("start", "start", start.0, start.1);
+        map.add_object("exit", "exit", exit.0, exit.1);
+    }
+    for (i, &(row, col)) in items.iter().enumerate() {
+        map.add_object(&format!("item {}", i + 1), "item", row, col);
+    }
+    map
+}
+
+//print_tiled
+//Purpose:
+//    Writes a Tiled map as tmx or tmj, adding the extension if the file name doesn't have it.
+fn print_tiled(map: &TiledMap, output_file_name: String, tiled_format: &str){
+    let extension = format!(".{}", tiled_format);
+    let file_name = if output_file_name.ends_with(&extension) {
+        output_file_name
+    } else {
+        output_file_name + &extension
+    };
+    let contents = if tiled_format == "tmx" { map.to_tmx() } else { map.to_tmj() };
+    let mut file = File::create(file_name).expect("Unable to create file");
+    file.write_all(contents.as_bytes()).unwrap();
+}
+
+//PictureFormat
+//Purpose:
+//    The kinds of picture a level can be written as, instead of text.
+//Notes:
+//  An image is drawn with square blocks of the given size in pixels.
+enum PictureFormat {
+    Image(usize),
+    Svg(SvgSettings),
+}
+
+//get_picture_format
+//Purpose:
+//    Reads from the command line which kind of picture to write, or None for text.
+//Notes:
+//  Without a format, giving a block size for images asks for an image.
+//  Svg uses the block size as its cell size, and its wall and background colors follow the image colors.
+fn get_picture_format(matches: &clap::ArgMatches, colors: &ImageColors) -> Option<PictureFormat> {
+    let block_size = matches.value_of("image").map(|block_size| block_size.parse::<usize>().unwrap());
+    let format = match matches.value_of("format") {
+        Some(format) => format,
+        None if block_size.is_some() => "image",
+        None => "text",
+    };
+    match format {
+        "image" => Some(PictureFormat::Image(block_size.unwrap_or(10).max(10))),
+        "svg" => {
+            let mut settings = SvgSettings::init(block_size.unwrap_or(10).max(1) as f64);
+            if let Some(stroke_width) = get_pixels_arg(matches, "stroke") {
+                settings.stroke_width = stroke_width;
             }
-        }
-        wall_matrix[0][2*my_maze.rows-2-2*i] = 1;
-        for j in 0..my_maze.columns {
-            if my_maze.maze_matrix[i][j].has_dir(Direction::North) == false {
-                wall_matrix[2*j+1][2*my_maze.rows-2-2*i] = 1;
+            if let Some(margin) = get_pixels_arg(matches, "margin") {
+                settings.margin = margin;
             }
-            wall_matrix[2*j+2][2*my_maze.rows-2-2*i] = 1;
-        }
+            settings.background_color = colors.floor;
+            settings.wall_color = colors.wall;
+            settings.water_color = colors.water;
+            settings.lava_color = colors.lava;
+            settings.ore_color = colors.ore;
+            Some(PictureFormat::Svg(settings))
+        },
+        _ => None,
+    }
+}
+
+//print_picture
+//Purpose:
+//    Writes a grid of colors, indexed by row then column with row 0 at the top, as the given kind of picture.
+fn print_picture(color_grid: &[Vec<image::Rgb<u8>>], output_file_name: String, picture_format: &PictureFormat){
+    match picture_format {
+        PictureFormat::Image(block_size) => save_block_image(color_grid, output_file_name, *block_size),
+        PictureFormat::Svg(settings) => save_svg(&svg::grid_to_svg(color_grid, settings), output_file_name),
     }
+}
+
+//save_svg
+//Purpose:
+//    Writes an svg image to a file, adding the .svg extension if the file name doesn't have it.
+fn save_svg(svg_image: &str, output_file_name: String){
+    let file_name = if output_file_name.ends_with(".svg") {
+        output_file_name
+    } else {
+        output_file_name + ".svg"
+    };
+    let mut file = File::create(file_name).expect("Unable to create file");
+    file.write_all(svg_image.as_bytes()).unwrap();
+}
+
+//get_mesh_settings
+//Purpose:
+//    Reads from the command line the size of a printed maze.
+fn get_mesh_settings(matches: &clap::ArgMatches) -> MeshSettings {
+    let mut settings = MeshSettings::init();
+    if let Some(wall_thickness) = matches.value_of("wall thickness") {
+        settings.wall_thickness = wall_thickness.parse::<f64>().unwrap();
+    }
+    if let Some(passage_width) = matches.value_of("passage width") {
+        settings.passage_width = passage_width.parse::<f64>().unwrap();
+    }
+    if let Some(wall_height) = matches.value_of("wall height") {
+        settings.wall_height = wall_height.parse::<f64>().unwrap();
+    }
+    if let Some(base_thickness) = matches.value_of("base thickness") {
+        settings.base_thickness = base_thickness.parse::<f64>().unwrap();
+    }
+    settings
+}
+
+//print_mesh
+//Purpose:
+//    Writes a mesh of the maze as stl or obj, adding the extension if the file name doesn't have it.
+fn print_mesh(my_maze: &Maze, output_file_name: String, mesh_format: &str, settings: &MeshSettings){
+    let my_mesh = match Mesh::from_maze(my_maze, settings) {
+        Ok(my_mesh) => my_mesh,
+        Err(err) => {
+            print!("Unable to make the mesh: {}\nExiting.", err);
+            process::exit(1);
+        },
+    };
+    let extension = format!(".{}", mesh_format);
+    let file_name = if output_file_name.ends_with(&extension) {
+        output_file_name
+    } else {
+        output_file_name + &extension
+    };
+    let contents = if mesh_format == "stl" { my_mesh.to_stl() } else { my_mesh.to_obj().into_bytes() };
+    let mut file = File::create(file_name).expect("Unable to create file");
+    file.write_all(&contents).unwrap();
+}
 
-    for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
-        if wall_matrix[(x/block_size_u32) as usize][(y/block_size_u32) as usize] == 1 {
-            *pixel = image::Rgb([0, 0, 0]);
-        }else {
-            *pixel = image::Rgb([255, 255, 255]);
+//print_book
+//Purpose:
+//    Generates the mazes of a puzzle book, puzzle i from the seed plus i, and writes the book as a pdf.
+fn print_book(rows: usize, columns: usize, method: GenerationType, seed: u64, matches: &clap::ArgMatches, output_file_name: String){
+    let num_puzzles = match matches.value_of("puzzles") {
+        Some(num_puzzles) => num_puzzles.parse::<usize>().unwrap(),
+        None => 10,
+    };
+    let mut settings = BookSettings::init();
+    if let Some(per_page) = matches.value_of("per page") {
+        settings.puzzles_per_page = per_page.parse::<usize>().unwrap();
+    }
+    if let Some(per_page) = matches.value_of("solutions per page") {
+        settings.solutions_per_page = per_page.parse::<usize>().unwrap();
+    }
+    let mut puzzles = Vec::new();
+    for i in 0..num_puzzles {
+        match Puzzle::generate(&format!("Maze {}", i + 1), rows, columns, method, seed.wrapping_add(i as u64)) {
+            Ok(puzzle) => puzzles.push(puzzle),
+            Err(err) => {
+                print!("Unable to make the puzzles: {}\nExiting.", err);
+                process::exit(1);
+            },
         }
     }
+    let contents = match book::book_to_pdf(&puzzles, &settings) {
+        Ok(contents) => contents,
+        Err(err) => {
+            print!("Unable to make the puzzle book: {}\nExiting.", err);
+            process::exit(1);
+        },
+    };
+    let file_name = if output_file_name.ends_with(".pdf") {
+        output_file_name
+    } else {
+        output_file_name + ".pdf"
+    };
+    let mut file = File::create(file_name).expect("Unable to create file");
+    file.write_all(&contents).unwrap();
+}
 
+//get_animation_settings
+//Purpose:
+//    Reads from the command line how a gif animation is drawn, with its colors following the image colors.
+fn get_animation_settings(matches: &clap::ArgMatches, colors: &ImageColors) -> AnimationSettings {
+    let block_size = matches.value_of("image").map(|block_size| block_size.parse::<usize>().unwrap());
+    let mut settings = AnimationSettings::init(block_size.unwrap_or(10).max(1));
+    if let Some(frame_skip) = matches.value_of("frame skip") {
+        settings.frame_skip = frame_skip.parse::<usize>().unwrap();
+    }
+    if let Some(frame_delay) = matches.value_of("frame delay") {
+        settings.frame_delay = frame_delay.parse::<u16>().unwrap();
+    }
+    settings.floor_color = colors.floor;
+    settings.wall_color = colors.wall;
+    settings
+}
+
+//create_gif_file
+//Purpose:
+//    Creates the file a gif animation is written to, adding the .gif extension if the file name doesn't have it.
+fn create_gif_file(output_file_name: String) -> BufWriter<File> {
+    let file_name = if output_file_name.ends_with(".gif") {
+        output_file_name
+    } else {
+        output_file_name + ".gif"
+    };
+    BufWriter::new(File::create(file_name).expect("Unable to create file"))
+}
+
+//save_block_image
+//Purpose:
+//    Writes a grid of colors as an image where each cell is a square block of pixels.
+//Notes:
+//  The grid is indexed by row then column, and row 0 is the top of the image.
+//  The image is saved as a jpeg if the file name asks for one, and as a png otherwise.
+fn save_block_image(color_grid: &[Vec<image::Rgb<u8>>], output_file_name: String, block_size: usize){
+    save_image(&raster::grid_to_image(color_grid, block_size), output_file_name);
+}
+
+//save_image
+//Purpose:
+//    Writes an image as a jpeg if the file name asks for one, and as a png otherwise.
+fn save_image(imgbuf: &image::RgbImage, output_file_name: String){
     if output_file_name.contains(".jpeg") || output_file_name.contains(".png") {
         imgbuf.save(output_file_name).unwrap();
     } else {
       imgbuf.save(output_file_name + ".png").unwrap();
     }
 }
+
+fn save_text(text: &str, output_file_name: String){
+    let mut file = File::create(output_file_name).expect("Unable to create file");
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+fn print_picture_maze(my_maze: &Maze, output_file_name: String, settings: &RasterSettings){
+    match raster::maze_to_image(my_maze, settings) {
+        Ok(imgbuf) => save_image(&imgbuf, output_file_name),
+        Err(err) => {
+            print!("Unable to draw the maze: {}\nExiting.", err);
+            process::exit(1);
+        },
+    }
+}
+
+//get_raster_settings
+//Purpose:
+//    Reads from the command line how a maze image is drawn, with walls and passages the block size unless given.
+fn get_raster_settings(matches: &clap::ArgMatches, block_size: usize, colors: &ImageColors) -> RasterSettings {
+    let mut settings = RasterSettings::init(block_size as f64);
+    if let Some(wall_width) = get_pixels_arg(matches, "wall px") {
+        settings.wall_width = wall_width;
+    }
+    if let Some(passage_width) = get_pixels_arg(matches, "passage px") {
+        settings.passage_width = passage_width;
+    }
+    if let Some(margin) = get_pixels_arg(matches, "image margin") {
+        settings.margin = margin;
+    }
+    settings.background_color = colors.background;
+    settings.wall_color = colors.wall;
+    settings.passage_color = colors.floor;
+    settings.antialias = matches.is_present("antialias");
+    settings
+}
+
+//get_lock_grid
+//Purpose:
+//    Returns the maze with its locks drawn as a grid of characters, laid out in the same way as the wall grid.
+//Notes:
+//  Walls are '#', the start and goal are 'S' and 'G', keys are lowercase letters and their doors the matching uppercase letters.
+fn get_lock_grid(locked_maze: &LockedMaze) -> Vec<Vec<char>> {
+    let my_maze = &locked_maze.maze;
+    let mut char_grid: Vec<Vec<char>> = my_maze.get_wall_grid().iter().map(|wall_row| {
+        wall_row.iter().map(|&is_wall| if is_wall { '#' } else { ' ' }).collect()
+    }).collect();
+    let grid_position = |(row, col): (usize,usize)| my_maze.get_grid_position(row, col);
+    for (i, door) in locked_maze.doors.iter().enumerate() {
+        let (grid_row, grid_col) = grid_position((door.row, door.col));
+        let (door_row, door_col) = match door.dir {
+            Direction::North => (grid_row - 1, grid_col),
+            Direction::South => (grid_row + 1, grid_col),
+            Direction::East => (grid_row, grid_col + 1),
+            Direction::West => (grid_row, grid_col - 1),
+        };
+        char_grid[door_row][door_col] = (b'A' + i as u8) as char;
+    }
+    for (i, &key) in locked_maze.keys.iter().enumerate() {
+        let (key_row, key_col) = grid_position(key);
+        char_grid[key_row][key_col] = (b'a' + i as u8) as char;
+    }
+    let (start_row, start_col) = grid_position(locked_maze.start);
+    char_grid[start_row][start_col] = 'S';
+    let (goal_row, goal_col) = grid_position(locked_maze.goal);
+    char_grid[goal_row][goal_col] = 'G';
+    char_grid
+}
+
+//print_char_grid
+//Purpose:
+//    Writes a grid of characters as a text file, one row per line.
+fn print_char_grid(char_grid: &[Vec<char>], output_file_name: String){
+    let file = File::create(output_file_name).expect("Unable to create file");
+    let mut f = BufWriter::new(file);
+    for char_row in char_grid {
+        let line: String = char_row.iter().collect();
+        f.write_all(line.as_bytes()).unwrap();
+        f.write_all("\n".as_bytes()).unwrap();
+    }
+    f.flush().unwrap();
+}
+
+//get_char_colors
+//Purpose:
+//    Colors a grid of characters by what each character stands for.
+//Notes:
+//  Keys (lowercase letters) and items ($) share the item color.
+fn get_char_colors(char_grid: &[Vec<char>], colors: &ImageColors) -> Vec<Vec<image::Rgb<u8>>> {
+    char_grid.iter().map(|char_row| {
+        char_row.iter().map(|&symbol| match symbol {
+            '#' => colors.wall,
+            '~' => colors.water,
+            '^' => colors.lava,
+            '*' => colors.ore,
+            'S' => colors.start,
+            'G' => colors.goal,
+            'A'..='Z' => colors.door,
+            'a'..='z' | '$' => colors.item,
+            _ => colors.floor,
+        }).collect()
+    }).collect()
+}
@@ -3,8 +3,13 @@
 //Purpose To create mazes
 //Notes:
 
-use std::collections::HashSet;
-use rand::Rng;
+use std::collections::{HashSet, VecDeque};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use crate::noise::hash_lattice;
+
+const EAST_EDGE_SALT: u64 = 0x4541_5354;
+const NORTH_EDGE_SALT: u64 = 0x4E4F_5254;
+const IMAGE_COLOR_TOLERANCE: i32 = 96; //how far in summed channels a pixel can be from the wall color and still be wall.
 
 ////////////////////
 //Custom Error handling code
@@ -13,6 +18,7 @@ use rand::Rng;
 #[derive(Debug)]
 pub enum MazeError {
     Syntax(String),
+    Image(image::ImageError),
 }
 
 use std::fmt;
@@ -22,18 +28,26 @@ impl fmt::Display for MazeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             MazeError::Syntax(ref err_string) => write!(f,"{}",err_string),
+            MazeError::Image(ref err) => write!(f,"{}",err),
         }
     }
 }
 
 impl Error for MazeError {
-    fn cause(&self) -> Option<&Error> {
+    fn cause(&self) -> Option<&dyn Error> {
         match *self {
             MazeError::Syntax(ref _err_string) => None,
+            MazeError::Image(ref err) => Some(err),
         }
     }
 }
 
+impl From<image::ImageError> for MazeError {
+    fn from(err: image::ImageError) -> MazeError {
+        MazeError::Image(err)
+    }
+}
+
 ////////////////////
 //Maze code
 ////////////////////
@@ -42,6 +56,7 @@ impl Error for MazeError {
 //Purpose:
 //    To denote which maze generation algorithm to use.
 #[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenerationType {
     Prim,
     Wilson,
@@ -52,6 +67,7 @@ pub enum GenerationType {
 //Purpose:
 //    To be able to record direction is a 2D square maze
 #[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     North,
     South,
@@ -93,18 +109,14 @@ impl Direction {
 }
 
 pub fn get_all_directions() -> Vec<Direction> {
-    let mut my_directions = Vec::new();
-    my_directions.push(Direction::North);
-    my_directions.push(Direction::South);
-    my_directions.push(Direction::East);
-    my_directions.push(Direction::West);
-    return my_directions;
+    vec![Direction::North, Direction::South, Direction::East, Direction::West]
 }
 
 //Compass
 //Purpose:
 //    To keep track of what direction one can move in a 2d square maze.
-#[derive(Debug,Clone,Copy)]
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Compass {
     //Stores the information of in which directions one can move from the given square.
     north: bool,
@@ -141,15 +153,41 @@ impl Compass {
 }
 
 #[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
-    row: usize,
-    col: usize,
+    pub row: usize,
+    pub col: usize,
 }
 
-#[derive(Debug)]
+//GenerationEvent
+//Purpose:
+//    To describe one step of generating a maze or a cave.
+//Notes:
+//  Carve opens the passage leaving the square from in the direction dir.
+//  Visit marks a square joining the maze, and Backtrack marks the backtrack algorithm leaving a dead end.
+//  CaveIteration(n) marks the end of the n-th iteration of a cave's cellular automaton, counting from 1.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GenerationEvent {
+    Carve{from: Point, dir: Direction},
+    Visit(Point),
+    Backtrack(Point),
+    CaveIteration(usize),
+}
+
+//Maze
+//Purpose:
+//    A rectangular maze, recording for each square the directions one can move from it.
+//Notes:
+//  Wrap is 0 for no wrapping, 1 when the east and west sides meet, and 2 when both pairs of sides meet, as in init_rect.
+//    It is kept with the maze, so a wrapping maze with no passage across an edge is still told apart from one which doesn't wrap.
+//  A passage leading off an edge which wraps comes back on the opposite side.
+#[derive(Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Maze {
     pub rows: usize,
 	pub columns: usize,
+	pub wrap: usize,
 	pub maze_matrix: Vec<Vec<Compass>>
 }
 
@@ -165,21 +203,39 @@ impl Maze {
     //    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
     //  The backtrack method requires a parameter which must be between 0.0 and 1.0, and affect the probablility
     pub fn init_rect(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType) -> Result<Maze,MazeError> {
+        Maze::init_rect_with_rng(my_rows, my_columns, wrap, method, &mut rand::thread_rng())
+    }
 
-		if (my_rows == 0) || (my_columns == 0) {
-			return Err(MazeError::Syntax("A maze requires non-zero dimensions.".to_string()));
-        }
-        
-        let matrix;
-        match method {
-            GenerationType::Prim => matrix = prims_algorithm(my_rows, my_columns, wrap),
-            GenerationType::Wilson => matrix = wilsons_algorithm(my_rows, my_columns, wrap),
-            GenerationType::Backtrack(straightness) => matrix = bias_recursive_backtrack_algorithm(my_rows, my_columns, wrap, straightness),
-        }
+    //init_rect_with_rng
+    //Purpose:
+    //    Creates a rectangular maze as init_rect does, drawing every random choice from the given generator.
+    //Notes:
+    //  Given a generator seeded the same way, the same maze is created every time.
+    pub fn init_rect_with_rng<R: Rng>(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType, rng: &mut R) -> Result<Maze,MazeError> {
+		Ok(MazeGenerator::init(my_rows, my_columns, wrap, method, rng)?.into_maze())
+    }
 
-		Ok(Maze{rows: my_rows, columns: my_columns, maze_matrix: matrix})
+    //init_rect_with_observer
+    //Purpose:
+    //    Creates a rectangular maze as init_rect does, calling the observer with the grid of passages after every passage is carved.
+    //Notes:
+    //  Squares not yet reached have no passages, so the observer sees the maze grow one passage at a time.
+    pub fn init_rect_with_observer(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType, observer: &mut dyn FnMut(&[Vec<Compass>])) -> Result<Maze,MazeError> {
+        let mut generator = MazeGenerator::init(my_rows, my_columns, wrap, method, rand::thread_rng())?;
+        while let Some(event) = generator.next() {
+            if let GenerationEvent::Carve{..} = event {
+                observer(generator.get_matrix());
+            }
+        }
+		Ok(generator.into_maze())
     }
 
+    //init_rect_with_bitmask
+    //Purpose:
+    //    Creates a rectangular maze as init_rect does, using only the squares marked true in the bitmask.
+    //Notes:
+    //  Squares marked false are left with no passages.
+    //  If the allowed squares are split into several regions, each region gets its own maze with no loops.
     pub fn init_rect_with_bitmask(my_rows: usize, my_columns: usize, wrap: usize, bitmask: Vec<Vec<bool>>, method: GenerationType) -> Result<Maze,MazeError> {
         
 		if (my_rows == 0) || (my_columns == 0) {
@@ -190,27 +246,338 @@ impl Maze {
             return Err(MazeError::Syntax("Given matrix does not match given dimensions.".to_string()));
         }
 
-        for i in 0..my_rows {
-            if my_columns != bitmask[i].len() {
+        for bitmask_row in bitmask.iter() {
+            if my_columns != bitmask_row.len() {
                 return Err(MazeError::Syntax("Given matrix does not match given dimensions.".to_string()));
             }
         }
 
-        get_starting_points(my_rows,my_columns,wrap,bitmask);
-        
-        let matrix;
-        match method {
-            GenerationType::Prim => matrix = prims_algorithm(my_rows, my_columns, wrap),
-            GenerationType::Wilson => matrix = wilsons_algorithm(my_rows, my_columns, wrap),
-            GenerationType::Backtrack(straightness) => matrix = bias_recursive_backtrack_algorithm(my_rows, my_columns, wrap, straightness),
+        let anchors = get_starting_points(my_rows,my_columns,wrap,&bitmask);
+
+		Ok(MazeGenerator::init_with_anchors(my_rows, my_columns, wrap, method, bitmask, anchors, rand::thread_rng()).into_maze())
+    }
+
+    //get_open_neighbors
+    //Purpose:
+    //    Returns the squares which can be reached from (row, col) in one step, along with the direction of each step.
+    //Notes:
+    //  A passage leading off an edge which wraps comes back on the other side.
+    //    One leading off an edge which doesn't wrap, such as the opening of a chunk, leaves the maze and is not returned.
+    pub fn get_open_neighbors(&self, row: usize, col: usize) -> Vec<(usize,usize,Direction)> {
+        let mut neighbors = Vec::new();
+        for dir in get_all_directions() {
+            if self.maze_matrix[row][col].has_dir(dir) {
+                if let Some(next) = get_cell_in_direction(self.rows, self.columns, row, col, dir, self.wrap) {
+                    neighbors.push((next.row,next.col,dir));
+                }
+            }
+        }
+        neighbors
+    }
+
+    //get_path
+    //Purpose:
+    //    Returns the squares on a shortest path from start to goal, including both, or None if the goal can't be reached.
+    //Pre-Conditions:
+    //    start and goal are squares of the maze.
+    pub fn get_path(&self, start: (usize,usize), goal: (usize,usize)) -> Option<Vec<(usize,usize)>> {
+        let mut parents: Vec<Vec<Option<(usize,usize)>>> = vec![vec![None; self.columns]; self.rows];
+        let mut visited = vec![vec![false; self.columns]; self.rows];
+        visited[start.0][start.1] = true;
+        let mut frontier = VecDeque::new();
+        frontier.push_back(start);
+        while let Some((row,col)) = frontier.pop_front() {
+            if (row,col) == goal {
+                let mut path = vec![goal];
+                while let Some(parent) = parents[path[path.len()-1].0][path[path.len()-1].1] {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (next_row,next_col,_dir) in self.get_open_neighbors(row, col) {
+                if !visited[next_row][next_col] {
+                    visited[next_row][next_col] = true;
+                    parents[next_row][next_col] = Some((row,col));
+                    frontier.push_back((next_row,next_col));
+                }
+            }
+        }
+        None
+    }
+
+    //get_wall_grid
+    //Purpose:
+    //    Returns the maze drawn as a grid of walls, laid out in the same way as the text output.
+    //Notes:
+    //  The grid has 2*rows + 1 rows and 2*columns + 1 columns, see get_grid_position for where each square is.
+    pub fn get_wall_grid(&self) -> Vec<Vec<bool>> {
+        let mut wall_grid = vec![vec![false; 2*self.columns+1]; 2*self.rows+1];
+        wall_grid[0][0] = true;
+        for i in 0..self.columns {
+            if !self.maze_matrix[self.rows-1][i].has_dir(Direction::North) {
+                wall_grid[0][2*i+1] = true;
+            }
+            wall_grid[0][2*i+2] = true;
         }
+        for i in (0..self.rows).rev() {
+            if !self.maze_matrix[i][0].has_dir(Direction::West) {
+                wall_grid[2*(self.rows-i)-1][0] = true;
+            }
+            for j in 0..self.columns{
+                if !self.maze_matrix[i][j].has_dir(Direction::East) {
+                    wall_grid[2*(self.rows-i)-1][2*j+2] = true;
+                }
+            }
+            wall_grid[2*(self.rows-i)][0] = true;
+            for j in 0..self.columns {
+                if !self.maze_matrix[i][j].has_dir(Direction::South) {
+                    wall_grid[2*(self.rows-i)][2*j+1] = true;
+                }
+                wall_grid[2*(self.rows-i)][2*j+2] = true;
+            }
+        }
+        wall_grid
+    }
 
-		Ok(Maze{rows: my_rows, columns: my_columns, maze_matrix: matrix})
+    //get_grid_position
+    //Purpose:
+    //    Returns where the square (row, col) is in the wall grid, as (row, column) with row 0 at the top.
+    pub fn get_grid_position(&self, row: usize, col: usize) -> (usize,usize) {
+        (2*(self.rows - row) - 1, 2*col + 1)
+    }
+
+    //from_text
+    //Purpose:
+    //    Reads a maze back from the text written by text::maze_to_text, where '#' is a wall and ' ' is open.
+    //Notes:
+    //  The text must have an odd number of lines of the same odd length, at least 3 of each.
+    //  Walls are always between two squares, so a passage is added to both of the squares it joins.
+    //    An opening in the outer wall is a wrapping passage to the square on the other side.
+    //  The text doesn't record wrap, so it is given as in init_rect. A maze which wraps need not have any openings in its outer wall,
+    //    but an opening is an error unless the maze wraps on that side.
+    //  Trailing blank lines and carriage returns are ignored.
+    pub fn from_text(text: &str, wrap: usize) -> Result<Maze,MazeError> {
+        let mut lines: Vec<Vec<char>> = text.lines().map(|line| line.trim_end_matches('\r').chars().collect()).collect();
+        while lines.last().map_or(false, |line| line.is_empty()) {
+            lines.pop();
+        }
+        let height = lines.len();
+        let width = if height == 0 { 0 } else { lines[0].len() };
+        if (height < 3) || (width < 3) || (height % 2 == 0) || (width % 2 == 0) {
+			return Err(MazeError::Syntax(format!("A maze needs an odd number of lines of odd length, at least 3 of each, found {} lines of length {}.", height, width)));
+        }
+        for (k, line) in lines.iter().enumerate() {
+            if line.len() != width {
+			    return Err(MazeError::Syntax(format!("Line {} has length {}, expected {}.", k + 1, line.len(), width)));
+            }
+            for (l, &symbol) in line.iter().enumerate() {
+                if (symbol != '#') && (symbol != ' ') {
+			        return Err(MazeError::Syntax(format!("Unexpected character '{}' on line {}, expected '#' or ' '.", symbol, k + 1)));
+                }
+                if (k % 2 == 0) && (l % 2 == 0) && (symbol != '#') {
+			        return Err(MazeError::Syntax(format!("Line {} is missing the wall corner at position {}.", k + 1, l + 1)));
+                }
+                if (k % 2 == 1) && (l % 2 == 1) && (symbol != ' ') {
+			        return Err(MazeError::Syntax(format!("Line {} has a wall where a square should be at position {}.", k + 1, l + 1)));
+                }
+            }
+        }
+
+        let my_rows = (height - 1)/2;
+        let my_columns = (width - 1)/2;
+        let mut matrix = vec![vec![Compass::init(); my_columns]; my_rows];
+        let mut needed_wrap = 0;
+        for i in 0..my_rows {
+            let square_line = &lines[2*(my_rows - i) - 1]; //the lines run from the last row down to row 0.
+            let wall_line = &lines[2*(my_rows - i) - 2];
+            for j in 0..my_columns {
+                if wall_line[2*j + 1] == ' ' { //the passage north of the square.
+                    if i == my_rows - 1 {
+                        needed_wrap = 2;
+                    }
+                    let north = (i + 1)%my_rows;
+                    matrix[i][j] = matrix[i][j].add_dir(Direction::North);
+                    matrix[north][j] = matrix[north][j].add_dir(Direction::South);
+                }
+                if square_line[2*j + 2] == ' ' { //the passage east of the square.
+                    if j == my_columns - 1 {
+                        needed_wrap = needed_wrap.max(1);
+                    }
+                    let east = (j + 1)%my_columns;
+                    matrix[i][j] = matrix[i][j].add_dir(Direction::East);
+                    matrix[i][east] = matrix[i][east].add_dir(Direction::West);
+                }
+            }
+            if square_line[0] == ' ' {
+                needed_wrap = needed_wrap.max(1);
+                matrix[i][0] = matrix[i][0].add_dir(Direction::West);
+                matrix[i][my_columns - 1] = matrix[i][my_columns - 1].add_dir(Direction::East);
+            }
+        }
+        for j in 0..my_columns {
+            if lines[height - 1][2*j + 1] == ' ' { //the passage south of row 0.
+                needed_wrap = 2;
+                matrix[0][j] = matrix[0][j].add_dir(Direction::South);
+                matrix[my_rows - 1][j] = matrix[my_rows - 1][j].add_dir(Direction::North);
+            }
+        }
+        if needed_wrap > wrap {
+			return Err(MazeError::Syntax(format!("The maze has openings in its outer wall which need a wrap of at least {}, but its wrap is {}.", needed_wrap, wrap)));
+        }
+		Ok(Maze{rows: my_rows, columns: my_columns, wrap, maze_matrix: matrix})
+    }
+
+    //from_image
+    //Purpose:
+    //    Reads a maze back from an image written by print_picture_maze.
+    //Notes:
+    //  See from_rgb_image for which images can be read.
+    pub fn from_image(file_name: &str, wrap: usize) -> Result<Maze,MazeError> {
+        let imgbuf = image::open(file_name)?.to_rgb();
+        Maze::from_rgb_image(&imgbuf, wrap)
+    }
+
+    //from_rgb_image
+    //Purpose:
+    //    Reads a maze back from an image of it drawn by raster::maze_to_image, with the last row at the top.
+    //Notes:
+    //  Corners of squares are always wall and squares are always open, and the diagonal from the top left corner crosses only those,
+    //    so walking down it crosses the margin, if there is one, then walls and squares in turn.
+    //    The lengths of the first runs of color give the margin and the wall and passage widths.
+    //  The margin and widths must be whole pixels, and the margin the same on every side and a different color from the walls.
+    //    A margin the same color as the squares and as wide as a passage looks just like a maze without one, and is read as none.
+    //  Each square of the wall grid is then read at its center, as wall if its color is closer to the wall color than the square color,
+    //    which allows for custom colors and the blur of jpeg images.
+    //  The image doesn't record wrap, so it is given as for from_text.
+    pub fn from_rgb_image(imgbuf: &image::RgbImage, wrap: usize) -> Result<Maze,MazeError> {
+        let (width, height) = (imgbuf.width() as usize, imgbuf.height() as usize);
+        let distance = |a: [u8; 3], b: [u8; 3]| (0..3).map(|c| (a[c] as i32 - b[c] as i32).abs()).sum::<i32>();
+        //the runs of similar color down the diagonal, as (color, length).
+        let mut runs: Vec<([u8; 3],usize)> = Vec::new();
+        for k in 0..width.min(height) {
+            let pixel = imgbuf.get_pixel(k as u32, k as u32).0;
+            match runs.last_mut() {
+                Some(run) if distance(pixel, run.0) <= IMAGE_COLOR_TOLERANCE => run.1 += 1,
+                _ => runs.push((pixel, 1)),
+            }
+        }
+        if runs.len() < 3 {
+			return Err(MazeError::Syntax("Unable to find the walls and squares of the maze on the diagonal from the top left corner.".to_string()));
+        }
+        //without a margin the runs go wall, square, wall, so the third run matches the first in both color and length.
+        let has_margin = (distance(runs[2].0, runs[0].0) > IMAGE_COLOR_TOLERANCE) || (runs[0].1 != runs[2].1);
+        let (margin, first_run) = if has_margin { (runs[0].1, 1) } else { (0, 0) };
+        if runs.len() < first_run + 3 {
+			return Err(MazeError::Syntax("Unable to find the walls and squares of the maze on the diagonal from the top left corner.".to_string()));
+        }
+        let (wall_color, wall_width) = runs[first_run];
+        let (square_color, passage_width) = runs[first_run + 1];
+
+        //a side with n squares is 2*margin + (n + 1)*wall_width + n*passage_width pixels long.
+        let get_num_squares = |length: usize| -> Option<usize> {
+            let inner = length.checked_sub(2*margin + wall_width)?;
+            if (inner > 0) && (inner % (wall_width + passage_width) == 0) { Some(inner/(wall_width + passage_width)) } else { None }
+        };
+        let (my_rows, my_columns) = match (get_num_squares(height), get_num_squares(width)) {
+            (Some(my_rows), Some(my_columns)) => (my_rows, my_columns),
+            _ => return Err(MazeError::Syntax(format!("The image is {} by {} pixels, which doesn't fit a maze with a {} pixel margin, {} pixel walls and {} pixel passages.",
+                width, height, margin, wall_width, passage_width))),
+        };
+        //the center of square k of the wall grid along either side, walls are at even k and passages at odd k.
+        let get_center = |k: usize| {
+            let width = if k % 2 == 0 { wall_width } else { passage_width };
+            margin + ((k + 1)/2)*wall_width + (k/2)*passage_width + width/2
+        };
+        let mut text = String::new();
+        for k in 0..(2*my_rows + 1) {
+            for l in 0..(2*my_columns + 1) {
+                let pixel = imgbuf.get_pixel(get_center(l) as u32, get_center(k) as u32).0;
+                if distance(pixel, wall_color) < distance(pixel, square_color) {
+                    text.push('#');
+                } else {
+                    text.push(' ');
+                }
+            }
+            text.push('\n');
+        }
+        Maze::from_text(&text, wrap)
+    }
+
+}
+
+////////////////////
+//Maze world code
+////////////////////
+
+//MazeWorld
+//Purpose:
+//    To describe an endless maze which is generated one chunk at a time.
+//Notes:
+//  Each chunk is a perfect maze created from a generator seeded by the world seed and the chunk's position.
+//  Every shared edge between two chunks has one opening, placed by the world seed and the edge's position,
+//    so both chunks agree on it without either having to be stored.
+//  Chunks don't wrap, so an opening is a passage out of the chunk into its neighbor, not to the other side of the chunk.
+//  Chunk x counts chunks east and chunk y counts chunks north, in the same way that columns and rows do.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MazeWorld {
+    pub chunk_rows: usize,
+    pub chunk_columns: usize,
+    pub method: GenerationType,
+    pub seed: u64,
+}
+
+impl MazeWorld {
+
+    pub fn init(chunk_rows: usize, chunk_columns: usize, method: GenerationType, seed: u64) -> Result<MazeWorld,MazeError> {
+		if (chunk_rows == 0) || (chunk_columns == 0) {
+			return Err(MazeError::Syntax("A chunk requires non-zero dimensions.".to_string()));
+        }
+        Ok(MazeWorld{chunk_rows, chunk_columns, method, seed})
+    }
+
+    //get_chunk
+    //Purpose:
+    //    Creates the chunk at (chunk_x, chunk_y), with openings to each of its four neighboring chunks.
+    pub fn get_chunk(&self, chunk_x: i64, chunk_y: i64) -> Maze {
+        let chunk_hash = hash_lattice(chunk_x, chunk_y, self.seed);
+        let second_hash = hash_lattice(chunk_x, chunk_y, chunk_hash);
+        let mut rng = XorShiftRng::from_seed([chunk_hash as u32, (chunk_hash >> 32) as u32, second_hash as u32, ((second_hash >> 32) as u32) | 1]);
+        //safe to unwrap since the dimensions were checked when the world was made.
+        let mut chunk = Maze::init_rect_with_rng(self.chunk_rows, self.chunk_columns, 0, self.method, &mut rng).unwrap();
+
+        let (last_row, last_col) = (self.chunk_rows - 1, self.chunk_columns - 1);
+        let east_row = self.get_opening(chunk_x, chunk_y, Direction::East);
+        let west_row = self.get_opening(chunk_x, chunk_y, Direction::West);
+        let north_col = self.get_opening(chunk_x, chunk_y, Direction::North);
+        let south_col = self.get_opening(chunk_x, chunk_y, Direction::South);
+        chunk.maze_matrix[east_row][last_col] = chunk.maze_matrix[east_row][last_col].add_dir(Direction::East);
+        chunk.maze_matrix[west_row][0] = chunk.maze_matrix[west_row][0].add_dir(Direction::West);
+        chunk.maze_matrix[last_row][north_col] = chunk.maze_matrix[last_row][north_col].add_dir(Direction::North);
+        chunk.maze_matrix[0][south_col] = chunk.maze_matrix[0][south_col].add_dir(Direction::South);
+        chunk
+    }
+
+    //get_opening
+    //Purpose:
+    //    Returns where the given edge of the chunk at (chunk_x, chunk_y) is open,
+    //    as a row for the east and west edges and as a column for the north and south edges.
+    pub fn get_opening(&self, chunk_x: i64, chunk_y: i64, dir: Direction) -> usize {
+        match dir {
+            Direction::East => (hash_lattice(chunk_x, chunk_y, self.seed ^ EAST_EDGE_SALT) % (self.chunk_rows as u64)) as usize,
+            Direction::North => (hash_lattice(chunk_x, chunk_y, self.seed ^ NORTH_EDGE_SALT) % (self.chunk_columns as u64)) as usize,
+            Direction::West => self.get_opening(chunk_x - 1, chunk_y, Direction::East),
+            Direction::South => self.get_opening(chunk_x, chunk_y - 1, Direction::North),
+        }
     }
 
 }
 
-fn get_starting_points(my_rows: usize, my_columns: usize, wrap: usize, bitmask: Vec<Vec<bool>>) -> Vec<Point>{
+//get_starting_points
+//Purpose:
+//    Returns one square from each connected region of the squares marked true in the bitmask.
+fn get_starting_points(my_rows: usize, my_columns: usize, wrap: usize, bitmask: &[Vec<bool>]) -> Vec<Point>{
     let mut anchor_points = Vec::new();
     let mut flood: HashSet<Point> = HashSet::new();
     let mut new_cells = HashSet::new();
@@ -236,59 +603,282 @@ fn get_starting_points(my_rows: usize, my_columns: usize, wrap: usize, bitmask:
                         }
                     }
                     new_cells = adjcent_cells.clone();
-                    if adjcent_cells.len() == 0 { //no new cells were added,
+                    if adjcent_cells.is_empty() { //no new cells were added,
                         added_cells = false;
                     }
                 }
             }
         }
     }
-    return anchor_points;
+    anchor_points
 }
 
-//prims_algorithm
+//MazeGenerator
 //Purpose:
-//    Returns a rectangular gird with a maze that uses every square with no loops.
-//Pre-Conditions:
-//    The variables max_rows and max_cols are non-zero.
+//    Generates a maze one step at a time, as an iterator over the events of its generation.
 //Notes:
-//  The alogirthm creates the maze uses Prim's algoirthm.
-//  Wrap indicates if the rectangle should be considered as having its sides meet up.
-//    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
-fn prims_algorithm(my_rows: usize, my_columns: usize, wrap: usize) -> Vec<Vec<Compass>> {
-    let mut path_matrix = vec![vec![Compass::init(); my_columns]; my_rows];
-    //the check matrix will keep track of which squares are already in the maze.
-    let mut check_matrix = vec![vec![false; my_columns]; my_rows];
-    let mut walls = Vec::new(); //list of walls
-    check_matrix[0][0] = true; //start with a square in the maze
-    walls.push((Point{row: 0, col: 0},Direction::North)); //add starting walls to maze (A wall is a cell and a direction.)
-    walls.push((Point{row: 0, col: 0},Direction::East));
-    if wrap >= 1 {
-        walls.push((Point{row: 0, col: 0},Direction::West));
-    }
-    if wrap >= 2 {
-        walls.push((Point{row: 0, col: 0},Direction::South));
-    }
-    while walls.len() != 0 { //while there are still walls.
-        //randomly select a wall
-        let choice = rand::thread_rng().gen_range(0, walls.len());
-        let current_cell = walls[choice].0;
-        let current_dir = walls[choice].1;
-        walls.remove(choice);//remove wall from list
-        let next_cell = get_cell_in_direction(my_rows,my_columns,current_cell.row,current_cell.col,current_dir,wrap);
-        if let Some(cell) = next_cell {
-            if !check_matrix[cell.row][cell.col] {//if there is a cell on the other side and it hasn't been visited yet.
-                walls.push((cell,Direction::East));//add walls of that cell
-                walls.push((cell,Direction::West));//(Note: one of these is unnecessary, but you must compare with current_dir)
-                walls.push((cell,Direction::North));//(the Wall which is not a wall will have no effect on the algoirthm,
-                walls.push((cell,Direction::South));//since the cell on the other side is already part of the maze)
-                check_matrix[cell.row][cell.col] = true;
-                path_matrix[current_cell.row][current_cell.col] = path_matrix[current_cell.row][current_cell.col].add_dir(current_dir);
-                path_matrix[cell.row][cell.col] = path_matrix[cell.row][cell.col].add_dir(current_dir.reverse());
+//  Each step of the algorithm carves at most one passage, and the generator only steps when it runs out of events,
+//    so generation can be paused between events, or stopped early by dropping the generator.
+//  A step which carves a passage gives a Carve event followed by a Visit of the square which joined the maze.
+//  Given a generator seeded the same way, the maze is the same as init_rect_with_rng makes.
+pub struct MazeGenerator<R: Rng> {
+    rows: usize,
+    columns: usize,
+    wrap: usize,
+    anchors: Vec<Point>,
+    bitmask: Vec<Vec<bool>>,
+    path_matrix: Vec<Vec<Compass>>,
+    check_matrix: Vec<Vec<bool>>,
+    state: GeneratorState,
+    events: VecDeque<GenerationEvent>,
+    rng: R,
+}
+
+//GeneratorState
+//Purpose:
+//    To record how far each algorithm has got in generating a maze.
+//Notes:
+//  Prim's algorithm keeps the list of walls which may lead to a square not yet in the maze.
+//  The backtrack algorithm keeps the index of the next anchor to start a trail from,
+//    the stack of squares on its current trail, and the direction it last moved.
+//  Wilson's algorithm keeps the next square to start a walk from, counted row by row,
+//    and the loop erased walk being carved into the maze along with how many of its passages are carved.
+//    If the row size or column size is two, the walk can't tell if it went north/south east/west, and will wrap around.
+enum GeneratorState {
+    Prim{walls: Vec<(Point,Direction)>},
+    Backtrack{straightness: f64, next_anchor: usize, cells: Vec<Point>, current_direction: Direction},
+    Wilson{next_square: usize, trail: Vec<Point>, trail_directions: Vec<Direction>, carved: usize},
+}
+
+impl<R: Rng> MazeGenerator<R> {
+
+    //init
+    //Purpose:
+    //    Starts generating a rectangular maze as init_rect does, drawing every random choice from the given generator.
+    //Notes:
+    //  Wrap indicates if the rectangle should be considered as having its sides meet up.
+    //    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
+    pub fn init(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType, rng: R) -> Result<MazeGenerator<R>,MazeError> {
+		if (my_rows == 0) || (my_columns == 0) {
+			return Err(MazeError::Syntax("A maze requires non-zero dimensions.".to_string()));
+        }
+        let bitmask = vec![vec![true; my_columns]; my_rows];
+        let anchors = vec![Point{row: 0, col: 0}];
+		Ok(MazeGenerator::init_with_anchors(my_rows, my_columns, wrap, method, bitmask, anchors, rng))
+    }
+
+    //init_with_anchors
+    //Purpose:
+    //    Starts generating a maze which uses only the squares marked true in the bitmask, growing from the anchors.
+    //Pre-Conditions:
+    //    The dimensions are non-zero and match the bitmask, and there is one anchor in each connected region of the bitmask.
+    fn init_with_anchors(my_rows: usize, my_columns: usize, wrap: usize, method: GenerationType, bitmask: Vec<Vec<bool>>, anchors: Vec<Point>, rng: R) -> MazeGenerator<R> {
+        //the check matrix will keep track of which squares are already in the maze, forbidden squares count as used.
+        let mut check_matrix = get_check_matrix(my_rows, my_columns, &bitmask);
+        let mut events = VecDeque::new();
+        let state = match method {
+            GenerationType::Prim => {
+                let mut walls = Vec::new(); //list of walls
+                for anchor in anchors.iter() {
+                    check_matrix[anchor.row][anchor.col] = true; //start with a square in the maze
+                    events.push_back(GenerationEvent::Visit(*anchor));
+                    for a_direction in get_all_directions() { //add starting walls to maze (A wall is a cell and a direction.)
+                        walls.push((*anchor,a_direction));
+                    }
+                }
+                GeneratorState::Prim{walls}
+            },
+            GenerationType::Backtrack(straightness) => {
+                GeneratorState::Backtrack{straightness, next_anchor: 0, cells: Vec::new(), current_direction: Direction::North}
+            },
+            GenerationType::Wilson => {
+                for anchor in anchors.iter() {
+                    check_matrix[anchor.row][anchor.col] = true;//start with a square in the maze
+                    events.push_back(GenerationEvent::Visit(*anchor));
+                }
+                GeneratorState::Wilson{next_square: 0, trail: Vec::new(), trail_directions: Vec::new(), carved: 0}
+            },
+        };
+        MazeGenerator{rows: my_rows, columns: my_columns, wrap, anchors, bitmask,
+            path_matrix: vec![vec![Compass::init(); my_columns]; my_rows], check_matrix,
+            state, events, rng}
+    }
+
+    //get_matrix
+    //Purpose:
+    //    Returns the passages carved so far, in the same form as the maze matrix.
+    //Notes:
+    //  Squares not yet in the maze have no passages.
+    pub fn get_matrix(&self) -> &[Vec<Compass>] {
+        &self.path_matrix
+    }
+
+    //into_maze
+    //Purpose:
+    //    Finishes generating the maze and returns it.
+    pub fn into_maze(mut self) -> Maze {
+        while self.next().is_some() {}
+        Maze{rows: self.rows, columns: self.columns, wrap: self.wrap, maze_matrix: self.path_matrix}
+    }
+
+    //step
+    //Purpose:
+    //    Does one step of the algorithm, queueing the events it causes, and returns false once the maze is finished.
+    fn step(&mut self) -> bool {
+        let (my_rows, my_columns, wrap) = (self.rows, self.columns, self.wrap);
+        match self.state {
+            //Prim's algorithm: join the square behind a random wall of the maze, if it isn't already in it.
+            GeneratorState::Prim{ref mut walls} => {
+                if walls.is_empty() { //the maze is finished when there are no walls left.
+                    return false;
+                }
+                //randomly select a wall
+                let choice = self.rng.gen_range(0, walls.len());
+                let (current_cell, current_dir) = walls.remove(choice);//remove wall from list
+                if let Some(cell) = get_cell_in_direction(my_rows,my_columns,current_cell.row,current_cell.col,current_dir,wrap) {
+                    if !self.check_matrix[cell.row][cell.col] {//if there is a cell on the other side and it hasn't been visited yet.
+                        walls.push((cell,Direction::East));//add walls of that cell
+                        walls.push((cell,Direction::West));//(Note: one of these is unnecessary, but you must compare with current_dir)
+                        walls.push((cell,Direction::North));//(the Wall which is not a wall will have no effect on the algoirthm,
+                        walls.push((cell,Direction::South));//since the cell on the other side is already part of the maze)
+                        self.check_matrix[cell.row][cell.col] = true;
+                        carve_passage(&mut self.path_matrix, current_cell, current_dir, cell);
+                        self.events.push_back(GenerationEvent::Carve{from: current_cell, dir: current_dir});
+                        self.events.push_back(GenerationEvent::Visit(cell));
+                    }
+                }
+            },
+            //biased recursive backtrack: extend the trail from the square at the top of the stack, preferring to go straight.
+            GeneratorState::Backtrack{straightness, ref mut next_anchor, ref mut cells, ref mut current_direction} => {
+                if cells.is_empty() { //start a trail from the next anchor not already reached from an earlier one.
+                    while (*next_anchor < self.anchors.len()) && self.check_matrix[self.anchors[*next_anchor].row][self.anchors[*next_anchor].col] {
+                        *next_anchor += 1;
+                    }
+                    if *next_anchor == self.anchors.len() {
+                        return false;
+                    }
+                    let anchor = self.anchors[*next_anchor];
+                    cells.push(anchor);
+                    self.check_matrix[anchor.row][anchor.col] = true; //start with a square in the maze
+                    self.events.push_back(GenerationEvent::Visit(anchor));
+                    let mut my_starting_directions = vec![Direction::North,Direction::East]; // start with a random direction (check for wrapping)
+                    if wrap >= 1 {
+                        my_starting_directions.push(Direction::West);
+                    }
+                    if wrap >= 2 {
+                        my_starting_directions.push(Direction::South);
+                    }
+                    let choice = self.rng.gen_range(0, my_starting_directions.len());
+                    *current_direction = my_starting_directions[choice];
+                    return true;
+                }
+                let current_cell = cells[cells.len()-1]; // grab the cell at the top of the stack
+                let mut nearby_cells = Vec::new();
+                let mut continue_in_current_direction = Vec::new();
+                let mut continue_in_other_direction = Vec::new();
+                for a_direction in get_all_directions() { // get valid moves
+                    if let Some(cell) = get_cell_in_direction(my_rows,my_columns,current_cell.row,current_cell.col,a_direction,wrap) {//if we can move in that direction
+                        if !self.check_matrix[cell.row][cell.col] { // and the cell has not yet been used, add it.
+                            nearby_cells.push((a_direction,cell));
+                            if a_direction == *current_direction {
+                                continue_in_current_direction.push((a_direction,cell));
+                            }else{
+                                continue_in_other_direction.push((a_direction,cell));
+                            }
+                        }
+                    }
+                }
+
+                if nearby_cells.is_empty() { //pop the cell of the stack since it is a deadend.
+                    let dead_end = cells.pop().unwrap();
+                    self.events.push_back(GenerationEvent::Backtrack(dead_end));
+                } else { //keep making a trail.
+                    let next_cell_data;
+                    if nearby_cells.len() == 1 { //there is only one option, so take that
+                        next_cell_data = nearby_cells[0];
+                    }else if !continue_in_current_direction.is_empty() { // if is possible to continue in a straight line.
+                        let prob = self.rng.gen_range(0.0,1.0);
+                        if prob <= 0.33 + (0.42*(straightness)) {
+                            next_cell_data = continue_in_current_direction[0];
+                        }else{
+                            let choice = self.rng.gen_range(0, continue_in_other_direction.len());
+                            next_cell_data = continue_in_other_direction[choice];
+                        }
+                    }else{
+                        let choice = self.rng.gen_range(0, continue_in_other_direction.len());
+                        next_cell_data = continue_in_other_direction[choice];
+                    }
+                    let next_cell = next_cell_data.1;
+                    *current_direction = next_cell_data.0;
+                    cells.push(next_cell);
+                    self.check_matrix[next_cell.row][next_cell.col] = true;
+                    carve_passage(&mut self.path_matrix, current_cell, *current_direction, next_cell);
+                    self.events.push_back(GenerationEvent::Carve{from: current_cell, dir: *current_direction});
+                    self.events.push_back(GenerationEvent::Visit(next_cell));
+                }
+            },
+            //Wilson's algorithm: carve the next passage of the current walk, or make a loop erased random walk
+            //  from the next square not in the maze until it meets the maze.
+            GeneratorState::Wilson{ref mut next_square, ref mut trail, ref mut trail_directions, ref mut carved} => {
+                if *carved + 1 < trail.len() {
+                    let k = *carved;
+                    self.check_matrix[trail[k].row][trail[k].col] = true;
+                    carve_passage(&mut self.path_matrix, trail[k], trail_directions[k], trail[k+1]);
+                    self.events.push_back(GenerationEvent::Carve{from: trail[k], dir: trail_directions[k]});
+                    self.events.push_back(GenerationEvent::Visit(trail[k]));
+                    *carved = k + 1;
+                    return true;
+                }
+                while (*next_square < my_rows*my_columns) && self.check_matrix[*next_square/my_columns][*next_square%my_columns] {
+                    *next_square += 1;
+                }
+                if *next_square == my_rows*my_columns {
+                    return false;
+                }
+                let mut current_square = Point{row: *next_square/my_columns, col: *next_square%my_columns};
+                trail.clear();
+                trail_directions.clear();
+                *carved = 0;
+                trail.push(current_square);
+                while !self.check_matrix[current_square.row][current_square.col] { //preform a loop erased random walk
+                    let neighbor_data = get_random_neighbor(my_rows,my_columns,current_square.row,current_square.col,wrap,&self.bitmask,&mut self.rng);
+                    current_square = neighbor_data.0;
+                    trail_directions.push(neighbor_data.1);
+                    if trail.contains(&current_square) { // if we loop
+                        let index = trail.iter().position(|&r| r == current_square).unwrap();
+                        trail.truncate(index+1); //erase loop
+                        trail_directions.truncate(index);
+                    } else {
+                        trail.push(current_square);
+                    }
+                }// we have met back up with squares from the maze, and the trail is carved in the steps that follow.
+                // note that since we start at a square not already in the maze the trail is at least 2.
+            },
+        }
+        true
+    }
+
+}
+
+impl<R: Rng> Iterator for MazeGenerator<R> {
+    type Item = GenerationEvent;
+
+    fn next(&mut self) -> Option<GenerationEvent> {
+        while self.events.is_empty() {
+            if !self.step() {
+                return None;
             }
         }
+        self.events.pop_front()
     }
-    return path_matrix;
+}
+
+//carve_passage
+//Purpose:
+//    Opens the passage leaving the square from in the direction dir, which leads to the square to.
+fn carve_passage(path_matrix: &mut [Vec<Compass>], from: Point, dir: Direction, to: Point) {
+    path_matrix[from.row][from.col] = path_matrix[from.row][from.col].add_dir(dir);
+    path_matrix[to.row][to.col] = path_matrix[to.row][to.col].add_dir(dir.reverse());
 }
 
 //get_cell_in_direction
@@ -318,160 +908,201 @@ fn get_cell_in_direction(max_rows: usize, max_cols: usize, row: usize, col: usiz
     };
 
     if wrap_level > wrap {
-        return None;
+        None
     }else {
-        return Some(Point{row: new_row, col: new_col});
+        Some(Point{row: new_row, col: new_col})
     }
 }
 
-//bias_recursive_backtrack_algorithm
+//get_random_neighbor
 //Purpose:
-//    Returns a rectangular gird with a maze that uses every square with no loops.
+//    Returns a random neighbor of a point (row,col) in a rectangular gird of size max_rows and max_cols.
 //Pre-Conditions:
-//    The variables max_rows and max_cols are non-zero.
+//    The point (row, col) is such that 0 <= row < max_rows and 0 <= col < max_cols.
+//    The conditions are such that the cells has a neighbor, if there is no wrapping and
+//    max_rows = max_cols = 1 the function will crash
+//    (But such a call should never happen in the first place.)
 //Notes:
-//  The alogirthm creates the maze uses a biased recursive backtrack algorithm
 //  Wrap indicates if the rectangle should be considered as having its sides meet up.
 //    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
-fn bias_recursive_backtrack_algorithm(my_rows: usize, my_columns: usize, wrap: usize, straightness: f64) -> Vec<Vec<Compass>> {
-    let mut path_matrix = vec![vec![Compass::init(); my_columns]; my_rows];
-    //the check matrix will keep track of which squares are already in the maze.
+//  Neighbors which are not marked true in the bitmask are never returned.
+fn get_random_neighbor<R: Rng>(max_rows: usize, max_cols: usize, row: usize, col: usize, wrap: usize, bitmask: &[Vec<bool>], rng: &mut R) -> (Point,Direction){
+    let mut neighbors = Vec::new();
+    if ((col + 1) < max_cols) || (wrap >= 1) {
+        neighbors.push((Point{row, col: (col+1)%max_cols},Direction::East));
+    }
+    if (col > 0) || (wrap >= 1) {
+        neighbors.push((Point{row, col: (col+max_cols-1)%max_cols},Direction::West));
+    }
+    if ((row + 1) < max_rows) || (wrap >= 2) {
+        neighbors.push((Point{row: (row+1)%max_rows, col},Direction::North));
+    }
+    if (row > 0) || (wrap >= 2) {
+        neighbors.push((Point{row: (row+max_rows-1)%max_rows, col},Direction::South));
+    }
+    neighbors.retain(|&(cell,_)| bitmask[cell.row][cell.col]);
+    let choice = rng.gen_range(0, neighbors.len());
+    neighbors[choice]
+}
+
+//get_check_matrix
+//Purpose:
+//    Returns a matrix where every square forbidden by the bitmask is marked as already used.
+fn get_check_matrix(my_rows: usize, my_columns: usize, bitmask: &[Vec<bool>]) -> Vec<Vec<bool>> {
     let mut check_matrix = vec![vec![false; my_columns]; my_rows];
-    let mut cells = Vec::new();
-    cells.push(Point{row: 0, col: 0});
-    check_matrix[0][0] = true; //start with a square in the maze
-    let mut my_starting_directions = vec![Direction::North,Direction::East]; // start with a random direction (check for wrapping)
-    if wrap >= 1 {
-        my_starting_directions.push(Direction::West);
-    }
-    if wrap >= 2 {
-        my_starting_directions.push(Direction::South);
-    }
-    let mut choice = rand::thread_rng().gen_range(0, my_starting_directions.len());
-    let mut current_direction = my_starting_directions[choice];
-    while cells.len() != 0 { //while there are still cells.
-        let current_cell = cells[cells.len()-1]; // grab the cell at the top of the stack
-        let mut nearby_cells = Vec::new();
-        let mut continue_in_current_direction = Vec::new();
-        let mut continue_in_other_direction = Vec::new();
-        let all_directions = get_all_directions();
-        for a_direction in all_directions.iter() { // get valid moves
-            if let Some(cell) = get_cell_in_direction(my_rows,my_columns,current_cell.row,current_cell.col,*a_direction,wrap) {//if we can move in that direction
-                if check_matrix[cell.row][cell.col] == false { // and the cell has not yet been used, add it.
-                    nearby_cells.push((a_direction,cell));
-                    if *a_direction == current_direction {
-                        continue_in_current_direction.push((a_direction,cell));
-                    }else{
-                        continue_in_other_direction.push((a_direction,cell));
-                    }
+    for i in 0..my_rows {
+        for j in 0..my_columns {
+            check_matrix[i][j] = !bitmask[i][j];
+        }
+    }
+    check_matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raster::{self, RasterSettings};
+    use crate::text;
+
+    //assert_perfect
+    //Purpose:
+    //    Checks that every square of the maze can be reached from (0, 0) and that the maze has no loops.
+    fn assert_perfect(maze: &Maze) {
+        let mut visited = vec![vec![false; maze.columns]; maze.rows];
+        visited[0][0] = true;
+        let mut frontier = vec![(0,0)];
+        let mut num_visited = 1;
+        let mut num_passages = 0;
+        while let Some((row,col)) = frontier.pop() {
+            for (next_row,next_col,_dir) in maze.get_open_neighbors(row, col) {
+                num_passages += 1;
+                if !visited[next_row][next_col] {
+                    visited[next_row][next_col] = true;
+                    num_visited += 1;
+                    frontier.push((next_row,next_col));
                 }
             }
         }
+        assert_eq!(num_visited, maze.rows*maze.columns);
+        //every passage is counted once from each end.
+        assert_eq!(num_passages, 2*(num_visited - 1));
+    }
 
-        if nearby_cells.len() == 0 { //pop the cell of the stack since it is a deadend.
-            cells.pop().unwrap();
-        } else { //keep making a trail.
-            let next_cell_data;
-            if nearby_cells.len() == 1 { //there is only one option, so take that
-                next_cell_data = nearby_cells[0];
-            }else if continue_in_current_direction.len() != 0 { // if is possible to continue in a straight line.
-                let prob = rand::thread_rng().gen_range(0.0,1.0);
-                if prob <= 0.33 + (0.42*(straightness)) {
-                    next_cell_data = continue_in_current_direction[0];
-                }else{
-                    choice = rand::thread_rng().gen_range(0, continue_in_other_direction.len());
-                    next_cell_data = continue_in_other_direction[choice];
-                }
-            }else{
-                choice = rand::thread_rng().gen_range(0, continue_in_other_direction.len());
-                next_cell_data = continue_in_other_direction[choice];
+    #[test]
+    fn chunks_are_deterministic() {
+        let world = MazeWorld::init(6, 8, GenerationType::Wilson, 42).unwrap();
+        assert_eq!(world.get_chunk(3, -2), world.get_chunk(3, -2));
+        let other_world = MazeWorld::init(6, 8, GenerationType::Wilson, 43).unwrap();
+        assert_ne!(world.get_chunk(3, -2), other_world.get_chunk(3, -2));
+    }
+
+    #[test]
+    fn neighboring_chunks_share_their_openings() {
+        let world = MazeWorld::init(6, 8, GenerationType::Backtrack(0.5), 7).unwrap();
+        let chunk = world.get_chunk(0, 0);
+        let east_chunk = world.get_chunk(1, 0);
+        let north_chunk = world.get_chunk(0, 1);
+        let east_rows: Vec<usize> = (0..6).filter(|&row| chunk.maze_matrix[row][7].has_dir(Direction::East)).collect();
+        let west_rows: Vec<usize> = (0..6).filter(|&row| east_chunk.maze_matrix[row][0].has_dir(Direction::West)).collect();
+        assert_eq!(east_rows.len(), 1);
+        assert_eq!(east_rows, west_rows);
+        let north_cols: Vec<usize> = (0..8).filter(|&col| chunk.maze_matrix[5][col].has_dir(Direction::North)).collect();
+        let south_cols: Vec<usize> = (0..8).filter(|&col| north_chunk.maze_matrix[0][col].has_dir(Direction::South)).collect();
+        assert_eq!(north_cols.len(), 1);
+        assert_eq!(north_cols, south_cols);
+    }
+
+    #[test]
+    fn chunk_openings_lead_out_of_the_chunk() {
+        let world = MazeWorld::init(5, 5, GenerationType::Prim, 11).unwrap();
+        for chunk_x in -2..3 {
+            for chunk_y in -2..3 {
+                let chunk = world.get_chunk(chunk_x, chunk_y);
+                assert_eq!(chunk.wrap, 0);
+                assert_perfect(&chunk);
             }
-            let next_cell = next_cell_data.1;
-            current_direction = *next_cell_data.0;
-            cells.push(next_cell);
-            path_matrix[current_cell.row][current_cell.col] = path_matrix[current_cell.row][current_cell.col].add_dir(current_direction);
-            path_matrix[next_cell.row][next_cell.col] = path_matrix[next_cell.row][next_cell.col].add_dir(current_direction.reverse());
-            check_matrix[next_cell.row][next_cell.col] = true;
         }
     }
-    return path_matrix;
-}
 
-//wilsons_algorithm
-//Purpose:
-//    Returns a rectangular gird with a maze that uses every square with no loops.
-//Pre-Conditions:
-//    The variables max_rows and max_cols are non-zero.
-//Notes:
-//  The alogirthm creates the maze uniformly at random.
-//  Wrap indicates if the rectangle should be considered as having its sides meet up.
-//    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
-//Bugs:
-//  If the row size or column size is two, the function will can't tell if the path went north/south east/west, and will wrap around.
-fn wilsons_algorithm(my_rows: usize, my_columns: usize, wrap: usize) -> Vec<Vec<Compass>> {
-    let mut path_matrix = vec![vec![Compass::init(); my_columns]; my_rows];
-    //the check matrix will keep track of which squares are already in the maze.
-    let mut check_matrix = vec![vec![false; my_columns]; my_rows];
-    check_matrix[0][0] = true;//start with a square in the maze
-    for row in 0..my_rows {
-        for col in 0..my_columns {
-            if !check_matrix[row][col] { // if the current square is not already in the maze
-                let mut trail = Vec::new();
-                let mut trail_directions = Vec::new();
-                let mut current_square = Point{row: row, col: col};
-                let mut current_direction;
-                trail.push(current_square);
-                while check_matrix[current_square.row][current_square.col] != true { //preform a loop erased random walk
-                    let neighbor_data = get_random_neighbor(my_rows,my_columns,current_square.row,current_square.col,wrap);
-                    current_square = neighbor_data.0;
-                    current_direction = neighbor_data.1;
-                    trail_directions.push(current_direction);
-                    if trail.contains(&current_square) { // if we loop
-                        let index = trail.iter().position(|&r| r == current_square).unwrap();
-                        trail.truncate(index+1); //erase loop
-                        trail_directions.truncate(index);
-                    } else {
-                        trail.push(current_square);
+    #[test]
+    fn generator_events_replay_into_the_maze() {
+        let methods = [GenerationType::Wilson, GenerationType::Prim, GenerationType::Backtrack(0.5)];
+        for &method in methods.iter() {
+            for wrap in 0..3 {
+                let seed = [7, 0x1234_5678, 0x9abc_def0, wrap as u32 + 1];
+                let mut generator = MazeGenerator::init(6, 9, wrap, method, XorShiftRng::from_seed(seed)).unwrap();
+                let mut replayed = vec![vec![Compass::init(); 9]; 6];
+                for event in generator.by_ref() {
+                    if let GenerationEvent::Carve{from, dir} = event {
+                        let to = get_cell_in_direction(6, 9, from.row, from.col, dir, wrap).unwrap();
+                        carve_passage(&mut replayed, from, dir, to);
                     }
-                }// we have met back up with squares from the maze.
-                //add new trail to the maze.
-
-                for k in 0..(trail.len()-1) { // note that since we start at a square not already in the maze tha trail is at least 2.
-                    check_matrix[trail[k].row][trail[k].col] = true;
-                    path_matrix[trail[k].row][trail[k].col] = path_matrix[trail[k].row][trail[k].col].add_dir(trail_directions[k]);
-                    path_matrix[trail[k+1].row][trail[k+1].col] = path_matrix[trail[k+1].row][trail[k+1].col].add_dir(trail_directions[k].reverse());
                 }
+                let maze = generator.into_maze();
+                assert_eq!(maze.maze_matrix, replayed, "{:?} with wrap {}", method, wrap);
+                assert_perfect(&maze);
+                let seeded_maze = Maze::init_rect_with_rng(6, 9, wrap, method, &mut XorShiftRng::from_seed(seed)).unwrap();
+                assert_eq!(maze, seeded_maze, "{:?} with wrap {}", method, wrap);
             }
         }
     }
-    return path_matrix;
-}
 
-//get_random_neighbor
-//Purpose:
-//    Returns a random neighbor of a point (row,col) in a rectangular gird of size max_rows and max_cols.
-//Pre-Conditions:
-//    The point (row, col) is such that 0 <= row < max_rows and 0 <= col < max_cols.
-//    The conditions are such that the cells has a neighbor, if there is no wrapping and
-//    max_rows = max_cols = 1 the function will crash
-//    (But such a call should never happen in the first place.)
-//Notes:
-//  Wrap indicates if the rectangle should be considered as having its sides meet up.
-//    A wrap of 0 means no wrapping, 1 means vertical wrapping, and 2 means wrapping on both pairs of sides.
-fn get_random_neighbor(max_rows: usize, max_cols: usize, row: usize, col: usize, wrap: usize) -> (Point,Direction){
-    let mut neighbors = Vec::new();
-    if ((col + 1) < max_cols) || (wrap >= 1) {
-        neighbors.push((Point{row: row, col: (col+1)%max_cols},Direction::East));
+    //get_seeded_maze
+    //Purpose:
+    //    Returns a maze made by a generator with a fixed seed.
+    fn get_seeded_maze(wrap: usize, seed: u32) -> Maze {
+        let mut rng = XorShiftRng::from_seed([seed, 0x1234_5678, 0x9abc_def0, 1]);
+        Maze::init_rect_with_rng(6, 9, wrap, GenerationType::Backtrack(0.5), &mut rng).unwrap()
     }
-    if (col > 0) || (wrap >= 1) {
-        neighbors.push((Point{row: row, col: (col+max_cols-1)%max_cols},Direction::West));
+
+    #[test]
+    fn text_round_trips() {
+        for wrap in 0..3 {
+            let maze = get_seeded_maze(wrap, 3);
+            assert_eq!(Maze::from_text(&text::maze_to_text(&maze), wrap).unwrap(), maze, "wrap {}", wrap);
+        }
+        //a torus with no passages across its edges looks just like a maze which doesn't wrap.
+        let mut torus = get_seeded_maze(0, 3);
+        torus.wrap = 2;
+        assert_eq!(Maze::from_text(&text::maze_to_text(&torus), 2).unwrap(), torus);
     }
-    if ((row + 1) < max_rows) || (wrap >= 2) {
-        neighbors.push((Point{row: (row+1)%max_rows, col: col},Direction::North));
+
+    #[test]
+    fn text_with_openings_it_cant_wrap_through_is_rejected() {
+        let mut maze = get_seeded_maze(0, 3);
+        maze.wrap = 1;
+        maze.maze_matrix[0][0] = maze.maze_matrix[0][0].add_dir(Direction::West);
+        maze.maze_matrix[0][8] = maze.maze_matrix[0][8].add_dir(Direction::East);
+        let text = text::maze_to_text(&maze);
+        assert!(Maze::from_text(&text, 0).is_err());
+        assert_eq!(Maze::from_text(&text, 1).unwrap(), maze);
     }
-    if (row > 0) || (wrap >= 2) {
-        neighbors.push((Point{row: (row+max_rows-1)%max_rows, col: col},Direction::South));
+
+    #[test]
+    fn image_round_trips() {
+        let mut plain = RasterSettings::init(3.0);
+        plain.wall_color = image::Rgb([20, 40, 60]);
+        let mut uneven = RasterSettings::init(2.0);
+        uneven.passage_width = 5.0;
+        uneven.margin = 4.0;
+        uneven.background_color = image::Rgb([200, 0, 0]);
+        uneven.antialias = true;
+        let mut blended_margin = RasterSettings::init(4.0);
+        blended_margin.passage_width = 3.0;
+        blended_margin.margin = 7.0;
+        for wrap in 0..3 {
+            let maze = get_seeded_maze(wrap, 3);
+            for settings in [plain, uneven, blended_margin].iter() {
+                let imgbuf = raster::maze_to_image(&maze, settings).unwrap();
+                assert_eq!(Maze::from_rgb_image(&imgbuf, wrap).unwrap(), maze, "wrap {} with {:?}", wrap, settings);
+            }
+        }
+    }
+
+    #[test]
+    fn image_with_the_wrong_size_is_rejected() {
+        let imgbuf = raster::maze_to_image(&get_seeded_maze(0, 3), &RasterSettings::init(3.0)).unwrap();
+        let cropped = image::RgbImage::from_fn(imgbuf.width() - 2, imgbuf.height(), |x, y| *imgbuf.get_pixel(x, y));
+        assert!(Maze::from_rgb_image(&cropped, 0).is_err());
     }
-    let choice = rand::thread_rng().gen_range(0, neighbors.len());
-    return neighbors[choice].clone();
 }
\ No newline at end of file
@@ -0,0 +1,257 @@
+//Date Created: 10/18/2026
+//Purpose To turn mazes into 3D meshes which can be printed
+//Notes:
+//  The mesh is a base plate with the walls of the maze standing on it, written as STL or OBJ.
+//  The maze is split into two layers of boxes, the base under every square of the wall grid and a wall box on every wall,
+//    and only the sides of boxes facing empty space are kept, so the surface is closed with every edge shared by two triangles.
+//  Only single level mazes exist, so each mesh holds one maze.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::maze::Maze;
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum MeshError {
+    Syntax(String),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MeshError::Syntax(ref err_string) => write!(f,"{}",err_string),
+        }
+    }
+}
+
+impl Error for MeshError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            MeshError::Syntax(ref _err_string) => None,
+        }
+    }
+}
+
+////////////////////
+//Mesh code
+////////////////////
+
+//MeshSettings
+//Purpose:
+//    To describe the size of a printed maze.
+//Notes:
+//  Sizes are in millimeters, the usual unit of STL files.
+//  Wall thickness and passage width are the widths of the walls and the gaps between them,
+//    so the maze is columns*passage_width + (columns + 1)*wall_thickness wide.
+//  The walls rise wall_height above the top of a base plate base_thickness thick, and every size must be positive.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct MeshSettings {
+    pub wall_thickness: f64,
+    pub passage_width: f64,
+    pub wall_height: f64,
+    pub base_thickness: f64,
+}
+
+impl MeshSettings {
+
+    pub fn init() -> MeshSettings {
+        MeshSettings{wall_thickness: 2.0, passage_width: 10.0, wall_height: 10.0, base_thickness: 2.0}
+    }
+
+}
+
+//Mesh
+//Purpose:
+//    A surface made of triangles, with each triangle given by the indices of its vertices.
+//Notes:
+//  The vertices of each triangle go counterclockwise when seen from outside the solid.
+//  The x axis runs east, the y axis north, and the z axis up from the bottom of the base plate.
+#[derive(Debug)]
+pub struct Mesh {
+    pub vertices: Vec<[f64;3]>,
+    pub triangles: Vec<[usize;3]>,
+}
+
+impl Mesh {
+
+    //from_maze
+    //Purpose:
+    //    Returns a mesh of the maze standing on a base plate.
+    //Notes:
+    //  Openings left by a wrapping maze are left as gaps in the outer wall.
+    pub fn from_maze(maze: &Maze, settings: &MeshSettings) -> Result<Mesh,MeshError> {
+        let sizes = [settings.wall_thickness, settings.passage_width, settings.wall_height, settings.base_thickness];
+        if sizes.iter().any(|size| size.is_nan() || *size <= 0.0) {
+			return Err(MeshError::Syntax("Every size of a printed maze must be positive.".to_string()));
+        }
+        let wall_grid = maze.get_wall_grid();
+        let grid_rows = wall_grid.len();
+        let grid_columns = wall_grid[0].len();
+        //positions of the lines between squares of the wall grid, walls are at even indices and passages at odd ones.
+        let get_lines = |length: usize| -> Vec<f64> {
+            let mut lines = vec![0.0];
+            for k in 0..length {
+                let width = if k % 2 == 0 { settings.wall_thickness } else { settings.passage_width };
+                lines.push(lines[k] + width);
+            }
+            lines
+        };
+        let x_lines = get_lines(grid_columns);
+        let y_lines = get_lines(grid_rows);
+        let z_lines = [0.0, settings.base_thickness, settings.base_thickness + settings.wall_height];
+
+        //layer 0 is the base plate, layer 1 the walls, and the wall grid's row 0 is the north edge.
+        let is_filled = |layer: usize, row: i64, col: i64| -> bool {
+            if (row < 0) || (col < 0) || (row as usize >= grid_rows) || (col as usize >= grid_columns) || (layer > 1) {
+                return false;
+            }
+            (layer == 0) || wall_grid[row as usize][col as usize]
+        };
+
+        let mut builder = MeshBuilder{vertices: Vec::new(), triangles: Vec::new(), vertex_indices: HashMap::new()};
+        for layer in 0..2 {
+            for i in 0..grid_rows {
+                for j in 0..grid_columns {
+                    let (row, col) = (i as i64, j as i64);
+                    if !is_filled(layer, row, col) {
+                        continue;
+                    }
+                    //corners of the box in lattice indices, y counts lines up from the south edge.
+                    let (x0, x1) = (j, j + 1);
+                    let (y0, y1) = (grid_rows - i - 1, grid_rows - i);
+                    let (z0, z1) = (layer, layer + 1);
+                    if !is_filled(layer, row, col - 1) { //west
+                        builder.add_quad([(x0,y0,z0), (x0,y0,z1), (x0,y1,z1), (x0,y1,z0)]);
+                    }
+                    if !is_filled(layer, row, col + 1) { //east
+                        builder.add_quad([(x1,y0,z0), (x1,y1,z0), (x1,y1,z1), (x1,y0,z1)]);
+                    }
+                    if !is_filled(layer, row + 1, col) { //south
+                        builder.add_quad([(x0,y0,z0), (x1,y0,z0), (x1,y0,z1), (x0,y0,z1)]);
+                    }
+                    if !is_filled(layer, row - 1, col) { //north
+                        builder.add_quad([(x0,y1,z0), (x0,y1,z1), (x1,y1,z1), (x1,y1,z0)]);
+                    }
+                    if layer == 0 || !is_filled(layer - 1, row, col) { //bottom
+                        builder.add_quad([(x0,y0,z0), (x0,y1,z0), (x1,y1,z0), (x1,y0,z0)]);
+                    }
+                    if !is_filled(layer + 1, row, col) { //top
+                        builder.add_quad([(x0,y0,z1), (x1,y0,z1), (x1,y1,z1), (x0,y1,z1)]);
+                    }
+                }
+            }
+        }
+
+        let vertices = builder.vertices.iter().map(|&(x,y,z)| [x_lines[x], y_lines[y], z_lines[z]]).collect();
+        Ok(Mesh{vertices, triangles: builder.triangles})
+    }
+
+    //to_stl
+    //Purpose:
+    //    Returns the mesh as a binary STL file.
+    pub fn to_stl(&self) -> Vec<u8> {
+        let mut stl = vec![0u8; 80]; //the header is unused.
+        stl.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+        for triangle in self.triangles.iter() {
+            let [a, b, c] = [self.vertices[triangle[0]], self.vertices[triangle[1]], self.vertices[triangle[2]]];
+            let normal = get_normal(a, b, c);
+            for point in [normal, a, b, c].iter() {
+                for &coordinate in point.iter() {
+                    stl.extend_from_slice(&(coordinate as f32).to_le_bytes());
+                }
+            }
+            stl.extend_from_slice(&0u16.to_le_bytes()); //attribute byte count.
+        }
+        stl
+    }
+
+    //to_obj
+    //Purpose:
+    //    Returns the mesh as a Wavefront OBJ file.
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        writeln!(obj, "# maze mesh, {} vertices and {} triangles, in millimeters", self.vertices.len(), self.triangles.len()).unwrap();
+        for vertex in self.vertices.iter() {
+            writeln!(obj, "v {} {} {}", vertex[0], vertex[1], vertex[2]).unwrap();
+        }
+        for triangle in self.triangles.iter() {
+            //obj counts vertices from 1.
+            writeln!(obj, "f {} {} {}", triangle[0] + 1, triangle[1] + 1, triangle[2] + 1).unwrap();
+        }
+        obj
+    }
+
+}
+
+//MeshBuilder
+//Purpose:
+//    To collect triangles while sharing vertices, which are kept as indices into the lines of the lattice until the mesh is finished.
+struct MeshBuilder {
+    vertices: Vec<(usize,usize,usize)>,
+    triangles: Vec<[usize;3]>,
+    vertex_indices: HashMap<(usize,usize,usize),usize>,
+}
+
+impl MeshBuilder {
+
+    //add_quad
+    //Purpose:
+    //    Adds a rectangle as two triangles, with its corners given counterclockwise as seen from outside.
+    fn add_quad(&mut self, corners: [(usize,usize,usize);4]) {
+        let indices: Vec<usize> = corners.iter().map(|&corner| self.get_vertex(corner)).collect();
+        self.triangles.push([indices[0], indices[1], indices[2]]);
+        self.triangles.push([indices[0], indices[2], indices[3]]);
+    }
+
+    fn get_vertex(&mut self, corner: (usize,usize,usize)) -> usize {
+        if let Some(&index) = self.vertex_indices.get(&corner) {
+            return index;
+        }
+        self.vertices.push(corner);
+        self.vertex_indices.insert(corner, self.vertices.len() - 1);
+        self.vertices.len() - 1
+    }
+
+}
+
+//get_normal
+//Purpose:
+//    Returns the unit normal of the triangle abc, pointing the way from which its vertices go counterclockwise.
+fn get_normal(a: [f64;3], b: [f64;3], c: [f64;3]) -> [f64;3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [u[1]*v[2] - u[2]*v[1], u[2]*v[0] - u[0]*v[2], u[0]*v[1] - u[1]*v[0]];
+    let length = (cross[0]*cross[0] + cross[1]*cross[1] + cross[2]*cross[2]).sqrt();
+    [cross[0]/length, cross[1]/length, cross[2]/length]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::GenerationType;
+
+    #[test]
+    fn every_edge_is_shared_by_two_triangles() {
+        for wrap in 0..3 {
+            let maze = Maze::init_rect(5, 7, wrap, GenerationType::Backtrack(0.5)).unwrap();
+            let mesh = Mesh::from_maze(&maze, &MeshSettings::init()).unwrap();
+            //each edge is counted in the direction it is walked, a closed surface walks every edge once each way.
+            let mut edges: HashMap<(usize,usize),usize> = HashMap::new();
+            for triangle in mesh.triangles.iter() {
+                for k in 0..3 {
+                    *edges.entry((triangle[k], triangle[(k + 1)%3])).or_insert(0) += 1;
+                }
+            }
+            for (&(a,b), &count) in edges.iter() {
+                assert_eq!(count, 1, "edge {:?} to {:?} is walked {} times", mesh.vertices[a], mesh.vertices[b], count);
+                assert_eq!(edges.get(&(b,a)), Some(&1), "edge {:?} to {:?} has no twin", mesh.vertices[a], mesh.vertices[b]);
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//Date Created: 10/18/2026
+//Purpose To create smooth random noise for caves and terrain
+//Notes:
+//  The noise is built from hashes of the lattice points, so the same seed always gives the same noise
+//    and any part of it can be computed without computing the rest.
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+//The frequency of each octave is double the last, so more than this many octaves would overflow.
+pub const MAX_OCTAVES: usize = 32;
+
+const GRADIENTS: [(f64,f64); 8] = [(1.0,0.0),(-1.0,0.0),(0.0,1.0),(0.0,-1.0),
+    (FRAC_1_SQRT_2,FRAC_1_SQRT_2),(-FRAC_1_SQRT_2,FRAC_1_SQRT_2),(FRAC_1_SQRT_2,-FRAC_1_SQRT_2),(-FRAC_1_SQRT_2,-FRAC_1_SQRT_2)];
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum NoiseError {
+    Syntax(String),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for NoiseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NoiseError::Syntax(ref err_string) => write!(f,"{}",err_string),
+        }
+    }
+}
+
+impl Error for NoiseError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            NoiseError::Syntax(ref _err_string) => None,
+        }
+    }
+}
+
+////////////////////
+//Noise code
+////////////////////
+
+//NoiseSettings
+//Purpose:
+//    To describe fractal noise.
+//Notes:
+//  Scale is the size in cells of the largest features, and each further octave adds detail at half the size and half the strength.
+//  Threshold is between 0.0 and 1.0, cells where the noise is at least the threshold become walls.
+//  Octaves is between 1 and MAX_OCTAVES.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoiseSettings {
+    pub octaves: usize,
+    pub scale: f64,
+    pub threshold: f64,
+    pub seed: u64,
+}
+
+impl NoiseSettings {
+
+    pub fn init(scale: f64, seed: u64) -> NoiseSettings {
+        NoiseSettings{octaves: 4, scale, threshold: 0.5, seed}
+    }
+
+}
+
+//noise_grid
+//Purpose:
+//    Returns a grid of fractal noise with values between 0.0 and 1.0.
+//Pre-Conditions:
+//    The variables my_rows and my_columns are non-zero, and scale is positive.
+//Notes:
+//  Wrap indicates if the rectangle should be considered as having its sides meet up.
+//    A wrap of 0 means no wrapping, 1 means the east and west sides meet (a tube), and 2 means wrapping on both pairs of sides (a torus).
+//  When a side wraps the scale is adjusted slightly so a whole number of features fit across, which lets the noise tile.
+//  Returns an error if the settings don't have between 1 and MAX_OCTAVES octaves.
+pub fn noise_grid(my_rows: usize, my_columns: usize, wrap: usize, settings: &NoiseSettings) -> Result<Vec<Vec<f64>>,NoiseError> {
+    let row_period = if wrap >= 2 { Some(get_period(my_rows, settings.scale)) } else { None };
+    let col_period = if wrap >= 1 { Some(get_period(my_columns, settings.scale)) } else { None };
+    let mut grid = vec![vec![0.0; my_columns]; my_rows];
+    for (i, grid_row) in grid.iter_mut().enumerate() {
+        for (j, cell) in grid_row.iter_mut().enumerate() {
+            let y = match row_period {
+                Some(period) => (i*period) as f64/(my_rows as f64),
+                None => i as f64/settings.scale,
+            };
+            let x = match col_period {
+                Some(period) => (j*period) as f64/(my_columns as f64),
+                None => j as f64/settings.scale,
+            };
+            *cell = fractal_noise(x, y, settings.octaves, col_period, row_period, settings.seed)?;
+        }
+    }
+    Ok(grid)
+}
+
+//fractal_noise
+//Purpose:
+//    Returns several octaves of gradient noise at the point (x, y) added together, scaled to lie between 0.0 and 1.0.
+//Notes:
+//  A period of Some(n) makes the first octave repeat every n units along that axis, and later octaves repeat along with it.
+//  Returns an error unless there are at least one and at most MAX_OCTAVES octaves.
+pub fn fractal_noise(x: f64, y: f64, octaves: usize, x_period: Option<usize>, y_period: Option<usize>, seed: u64) -> Result<f64,NoiseError> {
+    if (octaves == 0) || (octaves > MAX_OCTAVES) {
+		return Err(NoiseError::Syntax(format!("The noise must have between 1 and {} octaves.", MAX_OCTAVES)));
+    }
+    let mut total = 0.0;
+    let mut total_amplitude = 0.0;
+    for octave in 0..octaves {
+        //the periods are widened to 64 bits, since the last octave's can pass the limit of a 32 bit usize.
+        let frequency: u64 = 1 << octave;
+        let amplitude = 1.0/(frequency as f64);
+        let octave_x_period = x_period.map(|period| (period as u64)*frequency);
+        let octave_y_period = y_period.map(|period| (period as u64)*frequency);
+        let value = gradient_noise(x*(frequency as f64), y*(frequency as f64), octave_x_period, octave_y_period, seed.wrapping_add(octave as u64));
+        total += amplitude*value;
+        total_amplitude += amplitude;
+    }
+    // a single octave of gradient noise lies between about -0.71 and 0.71.
+    let normalized = (total/total_amplitude)/(2.0*FRAC_1_SQRT_2) + 0.5;
+    Ok(normalized.clamp(0.0, 1.0))
+}
+
+//gradient_noise
+//Purpose:
+//    Returns Perlin's gradient noise at the point (x, y).
+fn gradient_noise(x: f64, y: f64, x_period: Option<u64>, y_period: Option<u64>, seed: u64) -> f64 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let dx = x - x0;
+    let dy = y - y0;
+    let (ix, iy) = (x0 as i64, y0 as i64);
+    let corner_value = |corner_x: i64, corner_y: i64| {
+        let lattice_x = wrap_lattice(corner_x, x_period);
+        let lattice_y = wrap_lattice(corner_y, y_period);
+        let gradient = GRADIENTS[(hash_lattice(lattice_x, lattice_y, seed) % 8) as usize];
+        gradient.0*(x - corner_x as f64) + gradient.1*(y - corner_y as f64)
+    };
+    let fade_x = fade(dx);
+    let fade_y = fade(dy);
+    let bottom = lerp(corner_value(ix, iy), corner_value(ix + 1, iy), fade_x);
+    let top = lerp(corner_value(ix, iy + 1), corner_value(ix + 1, iy + 1), fade_x);
+    lerp(bottom, top, fade_y)
+}
+
+fn wrap_lattice(coordinate: i64, period: Option<u64>) -> i64 {
+    match period {
+        Some(period) => coordinate.rem_euclid(period as i64),
+        None => coordinate,
+    }
+}
+
+fn get_period(length: usize, scale: f64) -> usize {
+    let period = ((length as f64)/scale).round() as usize;
+    period.max(1)
+}
+
+fn fade(t: f64) -> f64 {
+    t*t*t*(t*(t*6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + t*(b - a)
+}
+
+//hash_lattice
+//Purpose:
+//    Returns a well mixed number for the lattice point (x, y), so each point gets its own random gradient.
+pub fn hash_lattice(x: i64, y: i64, seed: u64) -> u64 {
+    let mut hash = seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    hash = (hash ^ (hash >> 33)).wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash = (hash ^ (hash >> 33)).wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_deterministic() {
+        let mut settings = NoiseSettings::init(6.0, 17);
+        settings.octaves = MAX_OCTAVES;
+        assert_eq!(noise_grid(20, 30, 2, &settings).unwrap(), noise_grid(20, 30, 2, &settings).unwrap());
+        assert_eq!(noise_grid(20, 30, 0, &settings).unwrap(), noise_grid(20, 30, 0, &settings).unwrap());
+        let other_seed = NoiseSettings::init(6.0, 18);
+        assert_ne!(noise_grid(20, 30, 0, &settings).unwrap(), noise_grid(20, 30, 0, &other_seed).unwrap());
+    }
+
+    #[test]
+    fn octaves_out_of_range_are_rejected() {
+        for &octaves in [0, MAX_OCTAVES + 1].iter() {
+            assert!(fractal_noise(0.5, 0.5, octaves, None, None, 3).is_err(), "{} octaves", octaves);
+            let mut settings = NoiseSettings::init(6.0, 3);
+            settings.octaves = octaves;
+            assert!(noise_grid(4, 4, 0, &settings).is_err(), "{} octaves", octaves);
+        }
+        //every octave still tiles when the last one's period is past 32 bits.
+        let value = fractal_noise(0.3, 0.7, MAX_OCTAVES, Some(5), Some(3), 3).unwrap();
+        assert!((value - fractal_noise(5.3, 3.7, MAX_OCTAVES, Some(5), Some(3), 3).unwrap()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wrapped_noise_tiles() {
+        let (rows, columns) = (18, 25);
+        let mut settings = NoiseSettings::init(6.0, 29);
+        settings.octaves = 5;
+        let (row_period, col_period) = (get_period(rows, settings.scale), get_period(columns, settings.scale));
+        for wrap in 1..3 {
+            let grid = noise_grid(rows, columns, wrap, &settings).unwrap();
+            let y_period = if wrap >= 2 { Some(row_period) } else { None };
+            //the cell one period past each cell of the grid, found from the noise itself, has the same value.
+            for (i, grid_row) in grid.iter().enumerate() {
+                for (j, &cell) in grid_row.iter().enumerate() {
+                    let x = ((j + columns)*col_period) as f64/(columns as f64);
+                    let y = match y_period {
+                        Some(period) => ((i + rows)*period) as f64/(rows as f64),
+                        None => i as f64/settings.scale,
+                    };
+                    let value = fractal_noise(x, y, settings.octaves, Some(col_period), y_period, settings.seed).unwrap();
+                    assert!((cell - value).abs() < 1e-9, "wrap {} at ({}, {})", wrap, i, j);
+                }
+            }
+        }
+        //without wrapping the same shift gives different noise.
+        let grid = noise_grid(rows, columns, 0, &settings).unwrap();
+        let shifted = fractal_noise((columns as f64)/settings.scale, 0.0, settings.octaves, None, None, settings.seed).unwrap();
+        assert!((grid[0][0] - shifted).abs() > 1e-9);
+    }
+}
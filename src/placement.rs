@@ -0,0 +1,414 @@
+//Date Created: 10/18/2026
+//Purpose To scatter items and spawn points over mazes and caves
+//Notes:
+//  Cells are chosen by Poisson-disk sampling, every chosen cell is at least the minimum distance from the others.
+//  Only cells which can be reached from the start are ever chosen, so everything placed can be collected.
+
+use rand::Rng;
+use crate::maze::Maze;
+use crate::cave::{Cave, Tile};
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum PlacementError {
+    Syntax(String),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlacementError::Syntax(ref err_string) => write!(f,"{}",err_string),
+        }
+    }
+}
+
+impl Error for PlacementError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            PlacementError::Syntax(ref _err_string) => None,
+        }
+    }
+}
+
+////////////////////
+//Placement code
+////////////////////
+
+//PlacementSettings
+//Purpose:
+//    To describe how items are scattered.
+//Notes:
+//  Min_distance is the smallest distance in cells allowed between two items, and must be positive.
+//  At most max_count items are placed, fewer if no more cells are far enough from the others.
+//  Dead_end_weight makes the dead ends of a maze that many times more likely to be tried first than other squares.
+//  Open_area_weight favors cave floor far from walls, a cell's weight is its distance to the nearest wall
+//    raised to this power, so 0.0 treats all floor the same. It must be finite.
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacementSettings {
+    pub min_distance: f64,
+    pub max_count: usize,
+    pub dead_end_weight: f64,
+    pub open_area_weight: f64,
+}
+
+impl PlacementSettings {
+
+    pub fn init(min_distance: f64, max_count: usize) -> PlacementSettings {
+        PlacementSettings{min_distance, max_count, dead_end_weight: 1.0, open_area_weight: 0.0}
+    }
+
+}
+
+//place_in_maze
+//Purpose:
+//    Returns squares of the maze, as (row, column), to place items on.
+//Pre-Conditions:
+//    The start is a square of the maze.
+//Notes:
+//  The start itself is never chosen.
+//  Distances between items are measured across the edges of a wrapping maze.
+pub fn place_in_maze(maze: &Maze, start: (usize,usize), settings: &PlacementSettings) -> Result<Vec<(usize,usize)>,PlacementError> {
+    check_settings(settings)?;
+    if (start.0 >= maze.rows) || (start.1 >= maze.columns) {
+		return Err(PlacementError::Syntax("The start must be a square of the maze.".to_string()));
+    }
+    let mut reachable = vec![vec![false; maze.columns]; maze.rows];
+    reachable[start.0][start.1] = true;
+    let mut frontier = vec![start];
+    while let Some((row,col)) = frontier.pop() {
+        for (next_row,next_col,_dir) in maze.get_open_neighbors(row, col) {
+            if !reachable[next_row][next_col] {
+                reachable[next_row][next_col] = true;
+                frontier.push((next_row,next_col));
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (i, reachable_row) in reachable.iter().enumerate() {
+        for (j, &is_reachable) in reachable_row.iter().enumerate() {
+            if is_reachable && (i,j) != start {
+                let weight = if maze.get_open_neighbors(i, j).len() == 1 { settings.dead_end_weight } else { 1.0 };
+                candidates.push(((i,j),weight));
+            }
+        }
+    }
+    Ok(poisson_disk_sample(maze.rows, maze.columns, maze.wrap, candidates, settings))
+}
+
+//place_in_cave
+//Purpose:
+//    Returns floor cells of the cave, as (row, column), to place items on.
+//Pre-Conditions:
+//    The start is a floor cell of the cave.
+//Notes:
+//  Only plain floor is chosen, never water, lava or the start itself.
+//  Distances wrap around the sides of the cave that wrap.
+pub fn place_in_cave(cave: &Cave, start: (usize,usize), settings: &PlacementSettings) -> Result<Vec<(usize,usize)>,PlacementError> {
+    check_settings(settings)?;
+    if (start.0 >= cave.rows) || (start.1 >= cave.columns) || cave.is_wall(start.0,start.1) {
+		return Err(PlacementError::Syntax("The start must be a floor cell of the cave.".to_string()));
+    }
+    let label_matrix = cave.label_regions();
+    let start_label = label_matrix[start.0][start.1];
+    let wall_distance = get_wall_distance(cave);
+
+    let mut candidates = Vec::new();
+    for i in 0..cave.rows {
+        for j in 0..cave.columns {
+            if (label_matrix[i][j] == start_label) && (cave.tile_matrix[i][j] == Tile::Floor) && ((i,j) != start) {
+                let weight = (wall_distance[i][j] as f64).powf(settings.open_area_weight);
+                candidates.push(((i,j),weight));
+            }
+        }
+    }
+    Ok(poisson_disk_sample(cave.rows, cave.columns, cave.wrap, candidates, settings))
+}
+
+fn check_settings(settings: &PlacementSettings) -> Result<(),PlacementError> {
+    if settings.min_distance.is_nan() || settings.min_distance <= 0.0 {
+		return Err(PlacementError::Syntax("The minimum distance between items must be positive.".to_string()));
+    }
+    if settings.dead_end_weight.is_nan() || settings.dead_end_weight <= 0.0 {
+		return Err(PlacementError::Syntax("The dead end weight must be positive.".to_string()));
+    }
+    if !settings.open_area_weight.is_finite() {
+		return Err(PlacementError::Syntax("The open area weight must be a finite number.".to_string()));
+    }
+    Ok(())
+}
+
+//poisson_disk_sample
+//Purpose:
+//    Chooses cells from the candidates so no two chosen cells are closer than the minimum distance.
+//Notes:
+//  Candidates are tried one at a time in a random order where heavier candidates tend to come first,
+//    and a candidate is kept if it is far enough from every cell kept before it.
+//  Kept cells are sorted into buckets at least the minimum distance wide,
+//    so only the neighboring buckets need to be checked for each candidate.
+fn poisson_disk_sample(my_rows: usize, my_columns: usize, wrap: usize, candidates: Vec<((usize,usize),f64)>, settings: &PlacementSettings) -> Vec<(usize,usize)> {
+    let bucket_size = settings.min_distance.ceil() as usize;
+    let bucket_rows = (my_rows + bucket_size - 1)/bucket_size;
+    let bucket_columns = (my_columns + bucket_size - 1)/bucket_size;
+    let mut buckets: Vec<Vec<Vec<(usize,usize)>>> = vec![vec![Vec::new(); bucket_columns]; bucket_rows];
+
+    //weighted random order, each candidate is keyed by a uniform random number raised to one over its weight.
+    let mut ordered: Vec<(f64,(usize,usize))> = candidates.into_iter().filter(|&(_cell,weight)| weight > 0.0).map(|(cell,weight)| {
+        let key = rand::thread_rng().gen_range(0.0, 1.0f64).powf(1.0/weight);
+        (key,cell)
+    }).collect();
+    ordered.sort_by(|a,b| b.0.partial_cmp(&a.0).unwrap());
+
+    let mut chosen = Vec::new();
+    for (_key,(row,col)) in ordered {
+        if chosen.len() >= settings.max_count {
+            break;
+        }
+        let (bucket_row, bucket_col) = (row/bucket_size, col/bucket_size);
+        let mut is_far = true;
+        'search: for bucket_i in get_bucket_range(bucket_row, bucket_rows, wrap >= 2) {
+            for bucket_j in get_bucket_range(bucket_col, bucket_columns, wrap >= 1) {
+                for &(other_row,other_col) in buckets[bucket_i][bucket_j].iter() {
+                    let row_diff = get_axis_distance(row, other_row, my_rows, wrap >= 2);
+                    let col_diff = get_axis_distance(col, other_col, my_columns, wrap >= 1);
+                    if ((row_diff*row_diff + col_diff*col_diff) as f64) < settings.min_distance*settings.min_distance {
+                        is_far = false;
+                        break 'search;
+                    }
+                }
+            }
+        }
+        if is_far {
+            buckets[bucket_row][bucket_col].push((row,col));
+            chosen.push((row,col));
+        }
+    }
+    chosen
+}
+
+//get_bucket_range
+//Purpose:
+//    Returns the indices of the buckets along one axis which can hold a cell within one bucket width of the given bucket.
+//Notes:
+//  When the axis wraps the last bucket may be narrower than the others, so a cell can be close to a cell
+//    two buckets away across the wrap, and two buckets on each side are checked.
+fn get_bucket_range(index: usize, num_buckets: usize, wraps: bool) -> Vec<usize> {
+    let reach = if wraps { 2 } else { 1 };
+    let mut range = Vec::new();
+    for offset in 0..(2*reach + 1) {
+        let shifted = index + num_buckets*reach + offset - reach; //index + offset - reach, kept non-negative.
+        if !wraps && ((shifted < num_buckets*reach) || (shifted >= num_buckets*(reach + 1))) {
+            continue;
+        }
+        let neighbor = shifted % num_buckets;
+        if !range.contains(&neighbor) {
+            range.push(neighbor);
+        }
+    }
+    range
+}
+
+fn get_axis_distance(a: usize, b: usize, length: usize, wraps: bool) -> usize {
+    let diff = a.abs_diff(b);
+    if wraps {
+        return diff.min(length - diff);
+    }
+    diff
+}
+
+//get_wall_distance
+//Purpose:
+//    Returns for every cell of the cave the number of steps to the nearest wall, with walls at 0.
+//Notes:
+//  The edges of a cave which doesn't wrap there count as wall, so the floor along them is at 1.
+//  The breadth first search needs its frontier in order of distance,
+//    so every wall is queued before any of the floor along the edges.
+fn get_wall_distance(cave: &Cave) -> Vec<Vec<usize>> {
+    let mut distance = vec![vec![usize::MAX; cave.columns]; cave.rows];
+    let mut frontier = std::collections::VecDeque::new();
+    for (i, tile_row) in cave.tile_matrix.iter().enumerate() {
+        for (j, tile) in tile_row.iter().enumerate() {
+            if tile.is_wall() {
+                distance[i][j] = 0;
+                frontier.push_back((i,j));
+            }
+        }
+    }
+    for (i, distance_row) in distance.iter_mut().enumerate() {
+        for (j, cell_distance) in distance_row.iter_mut().enumerate() {
+            let on_edge = ((cave.wrap < 2) && (i == 0 || i == cave.rows - 1)) || ((cave.wrap < 1) && (j == 0 || j == cave.columns - 1));
+            if on_edge && (*cell_distance == usize::MAX) {
+                *cell_distance = 1;
+                frontier.push_back((i,j));
+            }
+        }
+    }
+    while let Some((row,col)) = frontier.pop_front() {
+        let neighbors = [((row + 1)%cave.rows,col), ((row + cave.rows - 1)%cave.rows,col), (row,(col + 1)%cave.columns), (row,(col + cave.columns - 1)%cave.columns)];
+        for &(next_row,next_col) in neighbors.iter() {
+            if distance[next_row][next_col] == usize::MAX {
+                distance[next_row][next_col] = distance[row][col] + 1;
+                frontier.push_back((next_row,next_col));
+            }
+        }
+    }
+    distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Compass, Direction};
+
+    //get_comb_maze
+    //Purpose:
+    //    Returns a maze with a corridor along row 0 and a dead end passage running north from it in every column.
+    //Notes:
+    //  The last row is left without passages, so its squares can't be reached from the corridor.
+    //  The corridor also runs across the east and west edges, making a loop.
+    fn get_comb_maze(my_rows: usize, my_columns: usize, wrap: usize) -> Maze {
+        let mut maze_matrix = vec![vec![Compass::init(); my_columns]; my_rows];
+        for square in maze_matrix[0].iter_mut() {
+            *square = square.add_dir(Direction::East).add_dir(Direction::West);
+        }
+        for (i, maze_row) in maze_matrix.iter_mut().enumerate().take(my_rows - 1) {
+            for square in maze_row.iter_mut() {
+                if i + 2 < my_rows {
+                    *square = square.add_dir(Direction::North);
+                }
+                if i > 0 {
+                    *square = square.add_dir(Direction::South);
+                }
+            }
+        }
+        Maze{rows: my_rows, columns: my_columns, wrap, maze_matrix}
+    }
+
+    //assert_spaced
+    //Purpose:
+    //    Checks that no two items are closer than the minimum distance, measuring across the edges which wrap.
+    fn assert_spaced(items: &[(usize,usize)], my_rows: usize, my_columns: usize, wrap: usize, min_distance: f64) {
+        for (k, &(row, col)) in items.iter().enumerate() {
+            for &(other_row, other_col) in items[(k + 1)..].iter() {
+                let mut row_diff = (row as f64 - other_row as f64).abs();
+                let mut col_diff = (col as f64 - other_col as f64).abs();
+                if wrap >= 2 {
+                    row_diff = row_diff.min(my_rows as f64 - row_diff);
+                }
+                if wrap >= 1 {
+                    col_diff = col_diff.min(my_columns as f64 - col_diff);
+                }
+                assert!(row_diff.hypot(col_diff) >= min_distance, "{:?} and {:?} are too close", (row, col), (other_row, other_col));
+            }
+        }
+    }
+
+    #[test]
+    fn maze_items_are_spaced_and_reachable() {
+        let (rows, columns) = (7, 10);
+        let settings = PlacementSettings::init(2.5, 100);
+        for wrap in 0..3 {
+            let maze = get_comb_maze(rows, columns, wrap);
+            for _i in 0..20 {
+                let items = place_in_maze(&maze, (0,0), &settings).unwrap();
+                assert!(!items.is_empty());
+                assert!(!items.contains(&(0,0)));
+                assert!(items.iter().all(|&(row, _col)| row < rows - 1), "{:?} has an item in the sealed row", items);
+                assert_spaced(&items, rows, columns, wrap, settings.min_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn heavy_dead_ends_are_filled_first() {
+        let (rows, columns) = (7, 10);
+        let maze = get_comb_maze(rows, columns, 0);
+        let mut settings = PlacementSettings::init(1.0, columns);
+        settings.dead_end_weight = 1e12;
+        for _i in 0..20 {
+            let items = place_in_maze(&maze, (0,0), &settings).unwrap();
+            //the only dead ends are the ends of the passages, one row below the sealed row.
+            assert_eq!(items.len(), columns);
+            assert!(items.iter().all(|&(row, _col)| row == rows - 2), "{:?} are not all dead ends", items);
+        }
+    }
+
+    #[test]
+    fn cave_items_avoid_the_start_water_and_lava() {
+        //walls down columns 3 and 9 split the cave, and the start's region runs across the east and west edges.
+        let (rows, columns) = (8, 12);
+        let walls: Vec<(usize,usize)> = (0..rows).flat_map(|i| vec![(i,3), (i,9)]).collect();
+        let mut cave = get_test_cave(rows, columns, 1, &walls);
+        cave.tile_matrix[2][1] = Tile::Water;
+        cave.tile_matrix[5][0] = Tile::Water;
+        cave.tile_matrix[3][11] = Tile::Lava;
+        let settings = PlacementSettings::init(2.0, 100);
+        for _i in 0..20 {
+            let items = place_in_cave(&cave, (0,0), &settings).unwrap();
+            assert!(!items.is_empty());
+            for &(row, col) in items.iter() {
+                assert!(!(3..=9).contains(&col), "{:?} is outside the start's region", (row, col));
+                assert_eq!(cave.tile_matrix[row][col], Tile::Floor);
+                assert_ne!((row, col), (0,0));
+            }
+            assert_spaced(&items, rows, columns, 1, settings.min_distance);
+        }
+    }
+
+    #[test]
+    fn weights_which_are_not_numbers_are_rejected() {
+        let cave = get_test_cave(5, 5, 0, &[]);
+        for &weight in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY].iter() {
+            let mut settings = PlacementSettings::init(1.0, 10);
+            settings.open_area_weight = weight;
+            assert!(place_in_cave(&cave, (2,2), &settings).is_err(), "open area weight {}", weight);
+        }
+        let maze = get_comb_maze(5, 5, 0);
+        for &weight in [f64::NAN, 0.0, -1.0].iter() {
+            let mut settings = PlacementSettings::init(1.0, 10);
+            settings.dead_end_weight = weight;
+            assert!(place_in_maze(&maze, (0,0), &settings).is_err(), "dead end weight {}", weight);
+        }
+    }
+
+    //get_test_cave
+    //Purpose:
+    //    Returns an open cave with walls only at the given cells.
+    fn get_test_cave(my_rows: usize, my_columns: usize, wrap: usize, walls: &[(usize,usize)]) -> Cave {
+        let mut cave_matrix = vec![vec![false; my_columns]; my_rows];
+        for &(i,j) in walls {
+            cave_matrix[i][j] = true;
+        }
+        Cave::from_walls(&cave_matrix, wrap)
+    }
+
+    #[test]
+    fn wall_distance_counts_the_nearest_wall() {
+        let cave = get_test_cave(5, 5, 0, &[(2,3)]);
+        let expected = vec![
+            vec![1, 1, 1, 1, 1],
+            vec![1, 2, 2, 1, 1],
+            vec![1, 2, 1, 0, 1],
+            vec![1, 2, 2, 1, 1],
+            vec![1, 1, 1, 1, 1],
+        ];
+        assert_eq!(get_wall_distance(&cave), expected);
+    }
+
+    #[test]
+    fn wall_distance_ignores_edges_which_wrap() {
+        //only the top and bottom edges count as wall, the wall at the west edge is next to the east edge.
+        let cave = get_test_cave(7, 6, 1, &[(3,0)]);
+        let distance = get_wall_distance(&cave);
+        assert_eq!(distance[3], vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(distance[0], vec![1; 6]);
+        assert_eq!(distance[6], vec![1; 6]);
+    }
+}
@@ -0,0 +1,339 @@
+//Date Created: 10/18/2026
+//Purpose To draw mazes as images with walls and passages of different widths
+//Notes:
+//  The wall grid is drawn with its even rows and columns, the walls and corner posts, wall_width pixels wide,
+//    and its odd rows and columns, the squares and the passages between them, passage_width pixels wide.
+//  Widths need not be whole pixels. Without anti-aliasing each pixel takes the color at its center,
+//    with it each pixel blends the colors it covers by how much of the pixel they cover.
+
+use crate::maze::Maze;
+
+////////////////////
+//Custom Error handling code
+////////////////////
+
+#[derive(Debug)]
+pub enum RasterError {
+    Syntax(String),
+}
+
+use std::fmt;
+use std::error::Error;
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RasterError::Syntax(ref err_string) => write!(f,"{}",err_string),
+        }
+    }
+}
+
+impl Error for RasterError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            RasterError::Syntax(ref _err_string) => None,
+        }
+    }
+}
+
+////////////////////
+//Raster code
+////////////////////
+
+//RasterSettings
+//Purpose:
+//    To describe how a maze is drawn as an image.
+//Notes:
+//  Sizes are in pixels. Wall and passage widths must be positive, and the margin can't be negative.
+//  The margin is the empty space left around the maze, and is filled with the background color.
+//  With equal wall and passage widths, no margin and no anti-aliasing, each square of the wall grid is a block of pixels.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct RasterSettings {
+    pub wall_width: f64,
+    pub passage_width: f64,
+    pub margin: f64,
+    pub background_color: image::Rgb<u8>,
+    pub wall_color: image::Rgb<u8>,
+    pub passage_color: image::Rgb<u8>,
+    pub antialias: bool,
+}
+
+impl RasterSettings {
+
+    pub fn init(block_size: f64) -> RasterSettings {
+        RasterSettings{wall_width: block_size, passage_width: block_size, margin: 0.0,
+            background_color: image::Rgb([255, 255, 255]), wall_color: image::Rgb([0, 0, 0]), passage_color: image::Rgb([255, 255, 255]),
+            antialias: false}
+    }
+
+}
+
+//grid_to_image
+//Purpose:
+//    Returns an image of a grid of colors where each cell is a square block of pixels.
+//Pre-Conditions:
+//    The grid is non-empty and rectangular.
+//Notes:
+//  The grid is indexed by row then column, and row 0 is the top of the image.
+pub fn grid_to_image(color_grid: &[Vec<image::Rgb<u8>>], block_size: usize) -> image::RgbImage {
+    let block_size_u32 = block_size as u32;
+    let mut imgbuf = image::RgbImage::new((block_size*color_grid[0].len()) as u32, (block_size*color_grid.len()) as u32);
+    for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
+        *pixel = color_grid[(y/block_size_u32) as usize][(x/block_size_u32) as usize];
+    }
+    imgbuf
+}
+
+//maze_to_image
+//Purpose:
+//    Returns an image of the maze, drawn in the same orientation as the text output, with the last row at the top.
+//Notes:
+//  The image is rounded up to whole pixels, and any part of a pixel past the maze is background.
+pub fn maze_to_image(maze: &Maze, settings: &RasterSettings) -> Result<image::RgbImage,RasterError> {
+    let widths = [settings.wall_width, settings.passage_width];
+    if widths.iter().any(|width| width.is_nan() || *width <= 0.0) {
+		return Err(RasterError::Syntax("The wall and passage widths must be positive.".to_string()));
+    }
+    if settings.margin.is_nan() || settings.margin < 0.0 {
+		return Err(RasterError::Syntax("The margin can't be negative.".to_string()));
+    }
+    let wall_grid = maze.get_wall_grid();
+    let x_lines = get_lines(wall_grid[0].len(), settings);
+    let y_lines = get_lines(wall_grid.len(), settings);
+    let width = (x_lines[x_lines.len() - 1] + settings.margin).ceil();
+    let height = (y_lines[y_lines.len() - 1] + settings.margin).ceil();
+    if (width > u32::MAX as f64) || (height > u32::MAX as f64) {
+		return Err(RasterError::Syntax(format!("An image can be at most {} pixels on a side.", u32::MAX)));
+    }
+    let x_coverage = get_coverage(&x_lines, width as usize, settings.antialias);
+    let y_coverage = get_coverage(&y_lines, height as usize, settings.antialias);
+
+    let mut imgbuf = image::RgbImage::new(width as u32, height as u32);
+    for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
+        let mut channels = [0.0; 3];
+        for &(grid_row, y_share) in y_coverage[y as usize].iter() {
+            for &(grid_col, x_share) in x_coverage[x as usize].iter() {
+                let color = match (grid_row, grid_col) {
+                    (Some(i), Some(j)) if wall_grid[i][j] => settings.wall_color,
+                    (Some(_), Some(_)) => settings.passage_color,
+                    _ => settings.background_color,
+                };
+                for (channel, &value) in channels.iter_mut().zip(color.0.iter()) {
+                    *channel += x_share*y_share*(value as f64);
+                }
+            }
+        }
+        *pixel = image::Rgb([channels[0].round() as u8, channels[1].round() as u8, channels[2].round() as u8]);
+    }
+    Ok(imgbuf)
+}
+
+//get_lines
+//Purpose:
+//    Returns the positions of the lines between squares of the wall grid along one side, starting after the margin.
+//Notes:
+//  Walls are at even indices and passages at odd ones, so square k lies between lines k and k + 1.
+fn get_lines(length: usize, settings: &RasterSettings) -> Vec<f64> {
+    let mut lines = vec![settings.margin];
+    for k in 0..length {
+        let width = if k % 2 == 0 { settings.wall_width } else { settings.passage_width };
+        lines.push(lines[k] + width);
+    }
+    lines
+}
+
+//get_coverage
+//Purpose:
+//    Returns for each pixel along one side the squares of the wall grid it covers, and the share of the pixel each covers.
+//Notes:
+//  None stands for the margin.
+//  Without anti-aliasing each pixel covers only the square under its center.
+fn get_coverage(lines: &[f64], num_pixels: usize, antialias: bool) -> Vec<Vec<(Option<usize>,f64)>> {
+    let num_squares = lines.len() - 1;
+    let get_square = |position: f64| -> Option<usize> {
+        //the number of lines at or before the position, one more than the square it is in.
+        let num_before = lines.partition_point(|&line| line <= position);
+        if (num_before == 0) || (num_before > num_squares) { None } else { Some(num_before - 1) }
+    };
+    let mut coverage = Vec::with_capacity(num_pixels);
+    for pixel in 0..num_pixels {
+        let (start, end) = (pixel as f64, (pixel + 1) as f64);
+        if !antialias {
+            coverage.push(vec![(get_square(start + 0.5), 1.0)]);
+            continue;
+        }
+        let mut shares = Vec::new();
+        let mut covered = 0.0;
+        let first_square = lines.partition_point(|&line| line <= start).saturating_sub(1);
+        for k in first_square..num_squares {
+            if lines[k] >= end {
+                break;
+            }
+            let share = end.min(lines[k+1]) - start.max(lines[k]);
+            if share > 0.0 {
+                shares.push((Some(k), share));
+                covered += share;
+            }
+        }
+        if covered < 1.0 {
+            shares.push((None, 1.0 - covered));
+        }
+        coverage.push(shares);
+    }
+    coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Direction, GenerationType};
+    use crate::cave::Cave;
+    use rand::{SeedableRng, XorShiftRng};
+
+    //get_north_wall_grid
+    //Purpose:
+    //    Returns the wall grid the way maze images were first drawn, taking the wall between two rows
+    //    from the north side of the lower row and the bottom edge from the north side of the top row.
+    fn get_north_wall_grid(maze: &Maze) -> Vec<Vec<bool>> {
+        let mut wall_grid = vec![vec![false; 2*maze.columns+1]; 2*maze.rows+1];
+        wall_grid[2*maze.rows][0] = true;
+        for i in 0..maze.columns {
+            if !maze.maze_matrix[maze.rows-1][i].has_dir(Direction::North) {
+                wall_grid[2*maze.rows][2*i+1] = true;
+            }
+            wall_grid[2*maze.rows][2*i+2] = true;
+        }
+        for i in 0..maze.rows {
+            if !maze.maze_matrix[i][0].has_dir(Direction::West) {
+                wall_grid[2*(maze.rows-i)-1][0] = true;
+            }
+            for j in 0..maze.columns {
+                if !maze.maze_matrix[i][j].has_dir(Direction::East) {
+                    wall_grid[2*(maze.rows-i)-1][2*j+2] = true;
+                }
+            }
+            wall_grid[2*(maze.rows-i)-2][0] = true;
+            for j in 0..maze.columns {
+                if !maze.maze_matrix[i][j].has_dir(Direction::North) {
+                    wall_grid[2*(maze.rows-i)-2][2*j+1] = true;
+                }
+                wall_grid[2*(maze.rows-i)-2][2*j+2] = true;
+            }
+        }
+        wall_grid
+    }
+
+    #[test]
+    fn maze_images_keep_their_first_layout() {
+        let methods = [GenerationType::Wilson, GenerationType::Prim, GenerationType::Backtrack(0.5)];
+        for (seed, &method) in methods.iter().enumerate() {
+            for wrap in 0..3 {
+                let mut rng = XorShiftRng::from_seed([seed as u32 + 1, wrap as u32 + 1, 3, 4]);
+                let maze = Maze::init_rect_with_rng(5, 8, wrap, method, &mut rng).unwrap();
+                let imgbuf = maze_to_image(&maze, &RasterSettings::init(1.0)).unwrap();
+                let wall_grid = get_north_wall_grid(&maze);
+                assert_eq!((imgbuf.width(), imgbuf.height()), (17, 11));
+                for (x, y, pixel) in imgbuf.enumerate_pixels() {
+                    let expected = if wall_grid[y as usize][x as usize] { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) };
+                    assert_eq!(*pixel, expected, "{:?} with wrap {} at ({}, {})", method, wrap, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cave_images_are_indexed_by_row_then_column() {
+        //a cave two rows tall and three columns wide, with walls at both ends of its top row.
+        let cave = Cave::from_walls(&[vec![true, false, true], vec![false, false, false]], 0);
+        let (black, white) = (image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let color_grid: Vec<Vec<image::Rgb<u8>>> = cave.get_wall_matrix().iter().map(|wall_row| {
+            wall_row.iter().map(|&is_wall| if is_wall { black } else { white }).collect()
+        }).collect();
+        let imgbuf = grid_to_image(&color_grid, 4);
+        assert_eq!((imgbuf.width(), imgbuf.height()), (12, 8));
+        assert_eq!(*imgbuf.get_pixel(0, 0), black);
+        assert_eq!(*imgbuf.get_pixel(4, 0), white);
+        assert_eq!(*imgbuf.get_pixel(11, 3), black);
+        assert_eq!(*imgbuf.get_pixel(11, 4), white);
+        assert_eq!(*imgbuf.get_pixel(0, 7), white);
+    }
+
+    //a maze of two squares in a row with a wall between them, drawn as "#####", "# # #" and "#####".
+    const TWO_SQUARES: &str = "#####\n# # #\n#####\n";
+
+    #[test]
+    fn walls_and_passages_can_have_different_widths() {
+        let maze = Maze::from_text(TWO_SQUARES, 0).unwrap();
+        let mut settings = RasterSettings::init(1.0);
+        settings.wall_width = 2.0;
+        settings.passage_width = 5.0;
+        let imgbuf = maze_to_image(&maze, &settings).unwrap();
+        //three walls and two passages across, two walls and one passage down.
+        assert_eq!((imgbuf.width(), imgbuf.height()), (16, 9));
+        let (black, white) = (image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let middle_row: Vec<image::Rgb<u8>> = (0..16).map(|x| *imgbuf.get_pixel(x, 4)).collect();
+        let mut expected = vec![black; 2];
+        expected.extend(vec![white; 5]);
+        expected.extend(vec![black; 2]);
+        expected.extend(vec![white; 5]);
+        expected.extend(vec![black; 2]);
+        assert_eq!(middle_row, expected);
+        assert_eq!(*imgbuf.get_pixel(4, 1), black);
+        assert_eq!(*imgbuf.get_pixel(4, 2), white);
+        assert_eq!(*imgbuf.get_pixel(4, 6), white);
+        assert_eq!(*imgbuf.get_pixel(4, 7), black);
+    }
+
+    #[test]
+    fn the_margin_is_filled_with_the_background() {
+        let maze = Maze::from_text(TWO_SQUARES, 0).unwrap();
+        let mut settings = RasterSettings::init(1.0);
+        settings.margin = 3.0;
+        settings.background_color = image::Rgb([255, 0, 0]);
+        let imgbuf = maze_to_image(&maze, &settings).unwrap();
+        assert_eq!((imgbuf.width(), imgbuf.height()), (11, 9));
+        for (x, y, pixel) in imgbuf.enumerate_pixels() {
+            let in_margin = !(3..8).contains(&x) || !(3..6).contains(&y);
+            assert_eq!(*pixel == settings.background_color, in_margin, "at ({}, {})", x, y);
+        }
+        assert_eq!(*imgbuf.get_pixel(3, 3), settings.wall_color);
+        assert_eq!(*imgbuf.get_pixel(4, 4), settings.passage_color);
+    }
+
+    #[test]
+    fn antialiased_pixels_blend_across_wall_edges() {
+        let maze = Maze::from_text(TWO_SQUARES, 0).unwrap();
+        let mut settings = RasterSettings::init(1.0);
+        settings.wall_width = 1.5;
+        settings.passage_width = 2.0;
+        //the lines across are at 0, 1.5, 3.5, 5, 7 and 8.5, and pixel row 2 lies inside the passages.
+        assert_eq!(*maze_to_image(&maze, &settings).unwrap().get_pixel(1, 2), image::Rgb([255, 255, 255]));
+        settings.antialias = true;
+        let imgbuf = maze_to_image(&maze, &settings).unwrap();
+        assert_eq!((imgbuf.width(), imgbuf.height()), (9, 5));
+        //half wall and half passage, or half wall and half background past the last line.
+        assert_eq!(*imgbuf.get_pixel(1, 2), image::Rgb([128, 128, 128]));
+        assert_eq!(*imgbuf.get_pixel(3, 2), image::Rgb([128, 128, 128]));
+        assert_eq!(*imgbuf.get_pixel(8, 2), image::Rgb([128, 128, 128]));
+        assert_eq!(*imgbuf.get_pixel(4, 2), image::Rgb([0, 0, 0]));
+        assert_eq!(*imgbuf.get_pixel(2, 2), image::Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        let maze = Maze::from_text(TWO_SQUARES, 0).unwrap();
+        for &width in [0.0, -1.0, f64::NAN].iter() {
+            let mut settings = RasterSettings::init(1.0);
+            settings.wall_width = width;
+            assert!(maze_to_image(&maze, &settings).is_err(), "wall width {}", width);
+            let mut settings = RasterSettings::init(1.0);
+            settings.passage_width = width;
+            assert!(maze_to_image(&maze, &settings).is_err(), "passage width {}", width);
+        }
+        for &margin in [-1.0, f64::NAN].iter() {
+            let mut settings = RasterSettings::init(1.0);
+            settings.margin = margin;
+            assert!(maze_to_image(&maze, &settings).is_err(), "margin {}", margin);
+        }
+    }
+}
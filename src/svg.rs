@@ -0,0 +1,217 @@
+//Date Created: 10/18/2026
+//Purpose To write mazes and caves as scalable vector images
+//Notes:
+//  Mazes are drawn with their walls as lines, everything else is drawn as a grid of colored squares,
+//    with neighboring squares of the same color merged into larger rectangles to keep the files small.
+
+use std::fmt::Write;
+use crate::maze::{Maze, Direction};
+use crate::cave::{Cave, Tile};
+
+//SvgSettings
+//Purpose:
+//    To describe how a vector image is drawn.
+//Notes:
+//  Sizes are in pixels, the cell size is the width of one square of a maze or one cell of a cave.
+//  The margin is the empty space left around the drawing, and is filled with the background color.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SvgSettings {
+    pub cell_size: f64,
+    pub stroke_width: f64,
+    pub margin: f64,
+    pub background_color: image::Rgb<u8>,
+    pub wall_color: image::Rgb<u8>,
+    pub water_color: image::Rgb<u8>,
+    pub lava_color: image::Rgb<u8>,
+    pub ore_color: image::Rgb<u8>,
+}
+
+impl SvgSettings {
+
+    pub fn init(cell_size: f64) -> SvgSettings {
+        SvgSettings{cell_size, stroke_width: cell_size/5.0, margin: cell_size,
+            background_color: image::Rgb([255, 255, 255]), wall_color: image::Rgb([0, 0, 0]),
+            water_color: image::Rgb([48, 96, 224]), lava_color: image::Rgb([224, 72, 16]), ore_color: image::Rgb([212, 175, 55])}
+    }
+
+}
+
+//maze_to_svg
+//Purpose:
+//    Returns an svg image of the maze, with each wall drawn as a line segment.
+//Notes:
+//  The maze is drawn in the same orientation as the text output, with the last row at the top.
+//  There is one line element for each segment from get_wall_segments, grouped to share their stroke.
+pub fn maze_to_svg(maze: &Maze, settings: &SvgSettings) -> String {
+    let get_position = |line: usize| settings.margin + (line as f64)*settings.cell_size;
+    let mut svg = get_svg_header(maze.rows, maze.columns, settings);
+    writeln!(svg, "<g stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"square\">",
+        get_hex_color(settings.wall_color), settings.stroke_width).unwrap();
+    for ((x0, y0), (x1, y1)) in get_wall_segments(maze) {
+        writeln!(svg, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+            get_position(x0), get_position(y0), get_position(x1), get_position(y1)).unwrap();
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+//get_wall_segments
+//Purpose:
+//    Returns the walls of the maze as line segments between corners of its squares, each as ((x, y), (x, y)).
+//Notes:
+//  Corner (x, y) is x squares from the left and y squares from the top, with the last row of the maze at the top.
+//  Walls in a straight line are joined into one segment, which runs left to right or top to bottom.
+//  Horizontal segments are listed before vertical ones.
+pub fn get_wall_segments(maze: &Maze) -> Vec<((usize,usize),(usize,usize))> {
+    let mut segments = Vec::new();
+    //horizontal walls, line k is the top of the k-th row from the top of the picture.
+    for k in 0..(maze.rows + 1) {
+        let has_wall = |j: usize| {
+            if k < maze.rows {
+                !maze.maze_matrix[maze.rows - 1 - k][j].has_dir(Direction::North)
+            } else {
+                !maze.maze_matrix[0][j].has_dir(Direction::South)
+            }
+        };
+        for (start, end) in get_runs(maze.columns, has_wall) {
+            segments.push(((start, k), (end, k)));
+        }
+    }
+    //vertical walls, line l is the left side of column l.
+    for l in 0..(maze.columns + 1) {
+        let has_wall = |k: usize| {
+            let row = maze.rows - 1 - k;
+            if l < maze.columns {
+                !maze.maze_matrix[row][l].has_dir(Direction::West)
+            } else {
+                !maze.maze_matrix[row][maze.columns - 1].has_dir(Direction::East)
+            }
+        };
+        for (start, end) in get_runs(maze.rows, has_wall) {
+            segments.push(((l, start), (l, end)));
+        }
+    }
+    segments
+}
+
+//cave_to_svg
+//Purpose:
+//    Returns an svg image of the cave, with walls, water, lava and ore drawn as rectangles over the background.
+pub fn cave_to_svg(cave: &Cave, settings: &SvgSettings) -> String {
+    let color_grid: Vec<Vec<image::Rgb<u8>>> = cave.tile_matrix.iter().map(|tile_row| {
+        tile_row.iter().map(|&tile| match tile {
+            Tile::Floor => settings.background_color,
+            Tile::Wall => settings.wall_color,
+            Tile::Water => settings.water_color,
+            Tile::Lava => settings.lava_color,
+            Tile::Ore => settings.ore_color,
+        }).collect()
+    }).collect();
+    grid_to_svg(&color_grid, settings)
+}
+
+//grid_to_svg
+//Purpose:
+//    Returns an svg image of a grid of colors, indexed by row then column with row 0 at the top.
+//Notes:
+//  Cells with the background color are left empty.
+//  The other cells are merged greedily, each rectangle is grown as far right as it can and then as far down as it can.
+pub fn grid_to_svg(color_grid: &[Vec<image::Rgb<u8>>], settings: &SvgSettings) -> String {
+    let rows = color_grid.len();
+    let columns = if rows == 0 { 0 } else { color_grid[0].len() };
+    let mut covered = vec![vec![false; columns]; rows];
+    let mut svg = get_svg_header(rows, columns, settings);
+    for i in 0..rows {
+        for j in 0..columns {
+            let color = color_grid[i][j];
+            if covered[i][j] || color == settings.background_color {
+                continue;
+            }
+            let mut width = 1;
+            while (j + width < columns) && !covered[i][j + width] && (color_grid[i][j + width] == color) {
+                width += 1;
+            }
+            let mut height = 1;
+            while (i + height < rows) && (j..(j + width)).all(|col| !covered[i + height][col] && (color_grid[i + height][col] == color)) {
+                height += 1;
+            }
+            for covered_row in covered[i..(i + height)].iter_mut() {
+                for is_covered in covered_row[j..(j + width)].iter_mut() {
+                    *is_covered = true;
+                }
+            }
+            writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                settings.margin + (j as f64)*settings.cell_size, settings.margin + (i as f64)*settings.cell_size,
+                (width as f64)*settings.cell_size, (height as f64)*settings.cell_size, get_hex_color(color)).unwrap();
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+//get_svg_header
+//Purpose:
+//    Returns the opening of an svg image large enough for the grid and its margins, filled with the background color.
+fn get_svg_header(rows: usize, columns: usize, settings: &SvgSettings) -> String {
+    let width = 2.0*settings.margin + (columns as f64)*settings.cell_size;
+    let height = 2.0*settings.margin + (rows as f64)*settings.cell_size;
+    let mut header = String::new();
+    writeln!(header, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", width, height, width, height).unwrap();
+    writeln!(header, "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>", width, height, get_hex_color(settings.background_color)).unwrap();
+    header
+}
+
+//get_runs
+//Purpose:
+//    Returns the runs of consecutive indices below length where has_wall is true, as (first, one past the last).
+fn get_runs<F: Fn(usize) -> bool>(length: usize, has_wall: F) -> Vec<(usize,usize)> {
+    let mut runs = Vec::new();
+    let mut run_start = None;
+    for index in 0..length {
+        match (has_wall(index), run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) => {
+                runs.push((start, index));
+                run_start = None;
+            },
+            _ => (),
+        }
+    }
+    if let Some(start) = run_start {
+        runs.push((start, length));
+    }
+    runs
+}
+
+fn get_hex_color(color: image::Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0[0], color.0[1], color.0[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a 2 by 3 maze, with the top line of text being the north side of row 1.
+    const FIXED_MAZE: &str = "#######\n#     #\n### # #\n#   # #\n#######\n";
+
+    #[test]
+    fn maze_walls_are_drawn_as_one_line_per_segment() {
+        let maze = Maze::from_text(FIXED_MAZE, 0).unwrap();
+        let segments = get_wall_segments(&maze);
+        assert_eq!(segments, vec![((0,0),(3,0)), ((0,1),(1,1)), ((0,2),(3,2)), ((0,0),(0,2)), ((2,1),(2,2)), ((3,0),(3,2))]);
+        let svg = maze_to_svg(&maze, &SvgSettings::init(10.0));
+        assert_eq!(svg.matches("<line ").count(), segments.len());
+        //the inner wall between the columns runs down the bottom row, one margin in from the corner.
+        assert!(svg.contains("<line x1=\"30\" y1=\"20\" x2=\"30\" y2=\"30\"/>"));
+    }
+
+    #[test]
+    fn cave_tiles_are_merged_into_rectangles() {
+        let walls = vec![vec![true, true, false], vec![true, true, false], vec![false, false, false]];
+        let cave = Cave::from_walls(&walls, 0);
+        let svg = cave_to_svg(&cave, &SvgSettings::init(10.0));
+        //the background, then the two by two block of wall.
+        assert_eq!(svg.matches("<rect ").count(), 2);
+        assert!(svg.contains("<rect x=\"10\" y=\"10\" width=\"20\" height=\"20\" fill=\"#000000\"/>"));
+    }
+}
@@ -0,0 +1,136 @@
+//Date Created: 10/18/2026
+//Purpose To draw mazes and caves as text with unicode box drawing and block characters
+//Notes:
+//  Box drawing puts a character on every corner of the squares of a maze, with lines to the corners it shares a wall with,
+//    so each square of the maze is one character wide and one line tall.
+//  Half blocks put two rows of cells in each line of text, so a cell is about as tall as it is wide in most terminal fonts.
+
+use crate::maze::Maze;
+use crate::cave::Cave;
+
+//maze_to_text
+//Purpose:
+//    Returns the maze drawn with '#' for wall and ' ' for open space, one character for each square of the wall grid.
+//Notes:
+//  This is the default text output, and Maze::from_text reads it back.
+pub fn maze_to_text(maze: &Maze) -> String {
+    let mut text = String::new();
+    for grid_row in maze.get_wall_grid() {
+        text.extend(grid_row.iter().map(|&is_wall| if is_wall { '#' } else { ' ' }));
+        text.push('\n');
+    }
+    text
+}
+
+//maze_to_box_text
+//Purpose:
+//    Returns the maze drawn with box drawing characters, with thin lines for walls.
+//Notes:
+//  The maze is drawn in the same orientation as the text output, with the last row at the top.
+//  The text has one more line than the maze has rows, and one more character per line than it has columns.
+pub fn maze_to_box_text(maze: &Maze) -> String {
+    let wall_grid = maze.get_wall_grid();
+    let mut text = String::new();
+    //the corners of the squares are the even positions of the wall grid.
+    for i in (0..wall_grid.len()).step_by(2) {
+        for j in (0..wall_grid[i].len()).step_by(2) {
+            let up = (i > 0) && wall_grid[i-1][j];
+            let down = (i + 1 < wall_grid.len()) && wall_grid[i+1][j];
+            let left = (j > 0) && wall_grid[i][j-1];
+            let right = (j + 1 < wall_grid[i].len()) && wall_grid[i][j+1];
+            text.push(get_box_char(up, down, left, right));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+//cave_to_half_blocks
+//Purpose:
+//    Returns the cave drawn with half block characters, two rows of cells to a line.
+//Notes:
+//  Only walls and floor are told apart, ore is drawn as wall and water and lava as floor.
+pub fn cave_to_half_blocks(cave: &Cave) -> String {
+    let wall_grid: Vec<Vec<bool>> = cave.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|tile| tile.is_wall()).collect()).collect();
+    grid_to_half_blocks(&wall_grid)
+}
+
+//grid_to_half_blocks
+//Purpose:
+//    Returns a grid of walls drawn with half block characters, two rows of cells to a line.
+//Notes:
+//  The grid is indexed by row then column, and row 0 is the top line.
+//  If the grid has an odd number of rows the bottom half of the last line is left empty.
+pub fn grid_to_half_blocks(wall_grid: &[Vec<bool>]) -> String {
+    let mut text = String::new();
+    for i in (0..wall_grid.len()).step_by(2) {
+        for j in 0..wall_grid[i].len() {
+            let top = wall_grid[i][j];
+            let bottom = (i + 1 < wall_grid.len()) && wall_grid[i+1][j];
+            text.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+//get_box_char
+//Purpose:
+//    Returns the box drawing character with lines leaving its center in the given directions.
+fn get_box_char(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => ' ',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╷',
+        (false, false, true, false) => '╴',
+        (false, false, false, true) => '╶',
+        (true, true, false, false) => '│',
+        (false, false, true, true) => '─',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::GenerationType;
+
+    //a 2 by 3 maze, with the top line of text being the north side of row 1.
+    const FIXED_MAZE: &str = "#######\n#     #\n### # #\n#   # #\n#######\n";
+
+    #[test]
+    fn box_text_has_a_corner_for_each_corner_of_the_squares() {
+        let maze = Maze::from_text(FIXED_MAZE, 0).unwrap();
+        assert_eq!(maze_to_text(&maze), FIXED_MAZE);
+        let box_text = maze_to_box_text(&maze);
+        assert_eq!(box_text, "┌──┐\n├╴╷│\n└─┴┘\n");
+        //the inner wall between the columns meets the south wall in a T-junction.
+        assert_eq!(box_text.lines().nth(2).unwrap().chars().nth(2), Some('┴'));
+
+        let maze = Maze::init_rect(7, 11, 0, GenerationType::Wilson).unwrap();
+        let box_text = maze_to_box_text(&maze);
+        let box_lines: Vec<&str> = box_text.lines().collect();
+        assert_eq!(box_lines.len(), maze.rows + 1);
+        assert!(box_lines.iter().all(|line| line.chars().count() == maze.columns + 1));
+    }
+
+    #[test]
+    fn half_blocks_put_two_rows_in_a_line() {
+        let walls = vec![vec![true, false], vec![true, true], vec![false, true]];
+        assert_eq!(grid_to_half_blocks(&walls), "█▄\n ▀\n");
+        assert_eq!(cave_to_half_blocks(&Cave::from_walls(&walls, 0)), "█▄\n ▀\n");
+    }
+}
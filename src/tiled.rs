@@ -0,0 +1,348 @@
+//Date Created: 10/18/2026
+//Purpose To export mazes and caves as maps for the Tiled level editor
+//Notes:
+//  Maps are written either as TMX (xml) or TMJ (json), with one tile layer and one object layer,
+//    and with the tileset embedded in the map.
+//  Tile ids in the tileset settings are counted from 0 within the tileset, as Tiled shows them.
+
+use std::fmt::Write;
+use crate::maze::Maze;
+use crate::cave::{Cave, Tile};
+
+const TILED_VERSION: &str = "1.10";
+pub const WALL_VARIANT_COUNT: usize = 47;
+
+//TilesetSettings
+//Purpose:
+//    To describe the tileset image and which of its tiles to use for each kind of cell.
+//Notes:
+//  If wall_variants is given, walls are autotiled, each wall uses the variant picked by which of its eight neighbors are walls,
+//    so walls can have inner and outer corners. Cells off the edge of the map count as walls.
+//  The variants are ordered as get_wall_variant describes, the usual 47 tile blob layout.
+#[derive(Debug,Clone,PartialEq)]
+pub struct TilesetSettings {
+    pub name: String,
+    pub image: String,
+    pub image_width: usize,
+    pub image_height: usize,
+    pub tile_width: usize,
+    pub tile_height: usize,
+    pub floor: u32,
+    pub wall: u32,
+    pub water: u32,
+    pub lava: u32,
+    pub ore: u32,
+    pub wall_variants: Option<[u32; WALL_VARIANT_COUNT]>,
+}
+
+impl TilesetSettings {
+
+    //init
+    //Purpose:
+    //    Returns settings for a tileset image of 16 pixel tiles, 8 across and 7 down,
+    //    with floor, wall, water, lava and ore as the first five tiles and room for the wall variants from the second row.
+    pub fn init() -> TilesetSettings {
+        TilesetSettings{name: "tiles".to_string(), image: "tiles.png".to_string(), image_width: 128, image_height: 112,
+            tile_width: 16, tile_height: 16, floor: 0, wall: 1, water: 2, lava: 3, ore: 4, wall_variants: None}
+    }
+
+    //with_autotiling
+    //Purpose:
+    //    Turns on autotiling, with the 47 wall variants being the tiles starting from first_variant.
+    pub fn with_autotiling(self, first_variant: u32) -> TilesetSettings {
+        let mut variants = [0; WALL_VARIANT_COUNT];
+        for (i, variant) in variants.iter_mut().enumerate() {
+            *variant = first_variant + i as u32;
+        }
+        TilesetSettings{wall_variants: Some(variants), ..self}
+    }
+
+    fn get_tile_id(&self, tile: Tile) -> u32 {
+        match tile {
+            Tile::Floor => self.floor,
+            Tile::Wall => self.wall,
+            Tile::Water => self.water,
+            Tile::Lava => self.lava,
+            Tile::Ore => self.ore,
+        }
+    }
+
+    fn get_columns(&self) -> usize {
+        self.image_width/self.tile_width
+    }
+
+    fn get_tile_count(&self) -> usize {
+        self.get_columns()*(self.image_height/self.tile_height)
+    }
+}
+
+//TiledObject
+//Purpose:
+//    To record a marked cell of the map, such as the start, the exit or an item.
+//Notes:
+//  Kind is written as the object's type in Tiled.
+#[derive(Debug,Clone,PartialEq)]
+pub struct TiledObject {
+    pub name: String,
+    pub kind: String,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct TiledMap {
+    pub rows: usize,
+    pub columns: usize,
+    pub tileset: TilesetSettings,
+    pub tile_matrix: Vec<Vec<u32>>,
+    pub objects: Vec<TiledObject>,
+}
+
+impl TiledMap {
+
+    //from_maze
+    //Purpose:
+    //    Creates a map of the maze drawn as a grid of walls, in the same layout as the text output.
+    //Notes:
+    //  Maze::get_grid_position gives where each square of the maze is on the map, for placing objects.
+    pub fn from_maze(maze: &Maze, tileset: &TilesetSettings) -> TiledMap {
+        let tiles: Vec<Vec<Tile>> = maze.get_wall_grid().iter().map(|wall_row| {
+            wall_row.iter().map(|&is_wall| if is_wall { Tile::Wall } else { Tile::Floor }).collect()
+        }).collect();
+        TiledMap::from_tiles(&tiles, tileset)
+    }
+
+    //from_cave
+    //Purpose:
+    //    Creates a map of the cave, with one tile for each cell.
+    pub fn from_cave(cave: &Cave, tileset: &TilesetSettings) -> TiledMap {
+        TiledMap::from_tiles(&cave.tile_matrix, tileset)
+    }
+
+    //from_tiles
+    //Purpose:
+    //    Creates a map from a grid of cave tiles, indexed by row then column with row 0 at the top.
+    //Pre-Conditions:
+    //    The grid is non-empty and rectangular.
+    pub fn from_tiles(tiles: &[Vec<Tile>], tileset: &TilesetSettings) -> TiledMap {
+        let rows = tiles.len();
+        let columns = tiles[0].len();
+        let is_wall = |row: Option<usize>, col: Option<usize>| {
+            match (row, col) {
+                (Some(row), Some(col)) if (row < rows) && (col < columns) => tiles[row][col].is_wall(),
+                _ => true,
+            }
+        };
+        let variant_table: Vec<usize> = (0..256).map(get_wall_variant).collect();
+        let mut tile_matrix = vec![vec![0; columns]; rows];
+        for i in 0..rows {
+            for j in 0..columns {
+                tile_matrix[i][j] = match (tiles[i][j], tileset.wall_variants) {
+                    (Tile::Wall, Some(variants)) => {
+                        //the neighbors clockwise from the north, each adding the next power of two if it is a wall.
+                        let neighbors = [(i.checked_sub(1), Some(j)), (i.checked_sub(1), Some(j + 1)), (Some(i), Some(j + 1)),
+                            (Some(i + 1), Some(j + 1)), (Some(i + 1), Some(j)), (Some(i + 1), j.checked_sub(1)),
+                            (Some(i), j.checked_sub(1)), (i.checked_sub(1), j.checked_sub(1))];
+                        let mut neighbor_mask = 0;
+                        for (bit, &(row, col)) in neighbors.iter().enumerate() {
+                            if is_wall(row, col) {
+                                neighbor_mask += 1 << bit;
+                            }
+                        }
+                        variants[variant_table[neighbor_mask]]
+                    },
+                    (tile, _) => tileset.get_tile_id(tile),
+                };
+            }
+        }
+        TiledMap{rows, columns, tileset: tileset.clone(), tile_matrix, objects: Vec::new()}
+    }
+
+    //add_object
+    //Purpose:
+    //    Marks the cell (row, col) of the map with an object of the given name and kind.
+    pub fn add_object(&mut self, name: &str, kind: &str, row: usize, col: usize) {
+        self.objects.push(TiledObject{name: name.to_string(), kind: kind.to_string(), row, col});
+    }
+
+    //to_tmx
+    //Purpose:
+    //    Returns the map in Tiled's xml format.
+    pub fn to_tmx(&self) -> String {
+        let tileset = &self.tileset;
+        let mut tmx = String::new();
+        writeln!(tmx, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+        writeln!(tmx, "<map version=\"{}\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"3\" nextobjectid=\"{}\">",
+            TILED_VERSION, self.columns, self.rows, tileset.tile_width, tileset.tile_height, self.objects.len() + 1).unwrap();
+        writeln!(tmx, " <tileset firstgid=\"1\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">",
+            escape_xml(&tileset.name), tileset.tile_width, tileset.tile_height, tileset.get_tile_count(), tileset.get_columns()).unwrap();
+        writeln!(tmx, "  <image source=\"{}\" width=\"{}\" height=\"{}\"/>", escape_xml(&tileset.image), tileset.image_width, tileset.image_height).unwrap();
+        writeln!(tmx, " </tileset>").unwrap();
+        writeln!(tmx, " <layer id=\"1\" name=\"Tiles\" width=\"{}\" height=\"{}\">", self.columns, self.rows).unwrap();
+        writeln!(tmx, "  <data encoding=\"csv\">").unwrap();
+        let data_rows: Vec<String> = self.get_gids().iter().map(|gid_row| {
+            gid_row.iter().map(|gid| gid.to_string()).collect::<Vec<String>>().join(",")
+        }).collect();
+        writeln!(tmx, "{}", data_rows.join(",\n")).unwrap();
+        writeln!(tmx, "</data>").unwrap();
+        writeln!(tmx, " </layer>").unwrap();
+        writeln!(tmx, " <objectgroup id=\"2\" name=\"Objects\">").unwrap();
+        for (i, object) in self.objects.iter().enumerate() {
+            writeln!(tmx, "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                i + 1, escape_xml(&object.name), escape_xml(&object.kind), object.col*tileset.tile_width, object.row*tileset.tile_height,
+                tileset.tile_width, tileset.tile_height).unwrap();
+        }
+        writeln!(tmx, " </objectgroup>").unwrap();
+        writeln!(tmx, "</map>").unwrap();
+        tmx
+    }
+
+    //to_tmj
+    //Purpose:
+    //    Returns the map in Tiled's json format.
+    pub fn to_tmj(&self) -> String {
+        let tileset = &self.tileset;
+        let gids: Vec<String> = self.get_gids().iter().flatten().map(|gid| gid.to_string()).collect();
+        let objects: Vec<String> = self.objects.iter().enumerate().map(|(i, object)| {
+            format!("    {{\"id\": {}, \"name\": \"{}\", \"type\": \"{}\", \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}, \"rotation\": 0, \"visible\": true}}",
+                i + 1, escape_json(&object.name), escape_json(&object.kind), object.col*tileset.tile_width, object.row*tileset.tile_height,
+                tileset.tile_width, tileset.tile_height)
+        }).collect();
+        let mut tmj = String::new();
+        writeln!(tmj, "{{").unwrap();
+        writeln!(tmj, "  \"type\": \"map\", \"version\": \"{}\", \"orientation\": \"orthogonal\", \"renderorder\": \"right-down\",", TILED_VERSION).unwrap();
+        writeln!(tmj, "  \"width\": {}, \"height\": {}, \"tilewidth\": {}, \"tileheight\": {}, \"infinite\": false, \"nextlayerid\": 3, \"nextobjectid\": {},",
+            self.columns, self.rows, tileset.tile_width, tileset.tile_height, self.objects.len() + 1).unwrap();
+        writeln!(tmj, "  \"tilesets\": [{{\"firstgid\": 1, \"name\": \"{}\", \"tilewidth\": {}, \"tileheight\": {}, \"tilecount\": {}, \"columns\": {}, \"image\": \"{}\", \"imagewidth\": {}, \"imageheight\": {}, \"margin\": 0, \"spacing\": 0}}],",
+            escape_json(&tileset.name), tileset.tile_width, tileset.tile_height, tileset.get_tile_count(), tileset.get_columns(),
+            escape_json(&tileset.image), tileset.image_width, tileset.image_height).unwrap();
+        writeln!(tmj, "  \"layers\": [").unwrap();
+        writeln!(tmj, "   {{\"id\": 1, \"name\": \"Tiles\", \"type\": \"tilelayer\", \"x\": 0, \"y\": 0, \"width\": {}, \"height\": {}, \"opacity\": 1, \"visible\": true,",
+            self.columns, self.rows).unwrap();
+        writeln!(tmj, "    \"data\": [{}]}},", gids.join(",")).unwrap();
+        writeln!(tmj, "   {{\"id\": 2, \"name\": \"Objects\", \"type\": \"objectgroup\", \"draworder\": \"topdown\", \"x\": 0, \"y\": 0, \"opacity\": 1, \"visible\": true,").unwrap();
+        writeln!(tmj, "    \"objects\": [\n{}\n    ]}}", objects.join(",\n")).unwrap();
+        writeln!(tmj, "  ]").unwrap();
+        writeln!(tmj, "}}").unwrap();
+        tmj
+    }
+
+    //get_gids
+    //Purpose:
+    //    Returns the tiles as Tiled's global ids, which count from 1 since the tileset is the first, leaving 0 as no tile.
+    fn get_gids(&self) -> Vec<Vec<u32>> {
+        self.tile_matrix.iter().map(|tile_row| tile_row.iter().map(|id| id + 1).collect()).collect()
+    }
+
+}
+
+//get_wall_variant
+//Purpose:
+//    Returns which of the 47 wall variants a wall uses, given which of its neighbors are walls.
+//Notes:
+//  The neighbor mask adds 1 for a wall to the north, 2 north east, 4 east, 8 south east,
+//    16 south, 32 south west, 64 west and 128 north west.
+//  A corner only matters when both of the sides beside it are walls, which leaves 47 different masks,
+//    and the variants are those masks in increasing order, so variant 0 is a lone wall and variant 46 is surrounded by walls.
+pub fn get_wall_variant(neighbor_mask: usize) -> usize {
+    let reduced_mask = reduce_neighbor_mask(neighbor_mask);
+    (0..reduced_mask).filter(|&mask| reduce_neighbor_mask(mask) == mask).count()
+}
+
+//reduce_neighbor_mask
+//Purpose:
+//    Clears the corners of the neighbor mask which don't have walls on both sides beside them.
+fn reduce_neighbor_mask(neighbor_mask: usize) -> usize {
+    let mut reduced_mask = neighbor_mask & 0b0101_0101;
+    for corner in [1, 3, 5, 7] {
+        let (before, after) = (1 << (corner - 1), 1 << ((corner + 1) % 8));
+        if (neighbor_mask & (1 << corner) != 0) && (neighbor_mask & before != 0) && (neighbor_mask & after != 0) {
+            reduced_mask |= 1 << corner;
+        }
+    }
+    reduced_mask
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::new();
+    for symbol in text.chars() {
+        match symbol {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            symbol if (symbol as u32) < 0x20 => write!(escaped, "\\u{:04x}", symbol as u32).unwrap(),
+            symbol => escaped.push(symbol),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //get_test_tiles
+    //Purpose:
+    //    Returns tiles drawn as text, where '#' is a wall, '~' is water and anything else is floor.
+    fn get_test_tiles(lines: &[&str]) -> Vec<Vec<Tile>> {
+        lines.iter().map(|line| line.chars().map(|symbol| match symbol {
+            '#' => Tile::Wall,
+            '~' => Tile::Water,
+            _ => Tile::Floor,
+        }).collect()).collect()
+    }
+
+    #[test]
+    fn there_are_47_wall_variants() {
+        let mut variants: Vec<usize> = (0..256).map(get_wall_variant).collect();
+        variants.sort();
+        variants.dedup();
+        assert_eq!(variants, (0..WALL_VARIANT_COUNT).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn walls_use_corner_variants() {
+        let tileset = TilesetSettings::init().with_autotiling(8);
+        //a lone wall.
+        let map = TiledMap::from_tiles(&get_test_tiles(&["...", ".#.", "..."]), &tileset);
+        assert_eq!(map.tile_matrix[1][1], 8);
+        //a wall surrounded by walls, where the cells off the edge count as walls.
+        let map = TiledMap::from_tiles(&get_test_tiles(&["###", "###", "###"]), &tileset);
+        assert_eq!(map.tile_matrix[1][1], 8 + 46);
+        assert_eq!(map.tile_matrix[0][0], 8 + 46);
+        //an inner corner, only the north east is open.
+        let map = TiledMap::from_tiles(&get_test_tiles(&["##.", "###", "###"]), &tileset);
+        assert_eq!(map.tile_matrix[1][1], 8 + 45);
+        assert_eq!(map.tile_matrix[0][2], tileset.floor);
+        //an outer corner, walls only to the east, south east and south.
+        let map = TiledMap::from_tiles(&get_test_tiles(&["...", ".##", ".##"]), &tileset);
+        assert_eq!(map.tile_matrix[1][1], 8 + get_wall_variant(4 + 8 + 16) as u32);
+        assert_ne!(get_wall_variant(4 + 8 + 16), get_wall_variant(4 + 16));
+    }
+
+    #[test]
+    fn gids_and_objects_are_placed_by_tile() {
+        let mut tileset = TilesetSettings::init();
+        tileset.tile_width = 16;
+        tileset.tile_height = 8;
+        let mut map = TiledMap::from_tiles(&get_test_tiles(&["#.~", "..#"]), &tileset);
+        map.add_object("start", "start", 1, 0);
+        map.add_object("exit", "exit", 0, 1);
+        map.add_object("item 1", "item", 1, 2);
+
+        let tmx = map.to_tmx();
+        assert!(tmx.contains("2,1,3,\n1,1,2\n"));
+        assert!(tmx.contains("name=\"start\" type=\"start\" x=\"0\" y=\"8\""));
+        assert!(tmx.contains("name=\"exit\" type=\"exit\" x=\"16\" y=\"0\""));
+        assert!(tmx.contains("name=\"item 1\" type=\"item\" x=\"32\" y=\"8\""));
+
+        let tmj = map.to_tmj();
+        assert!(tmj.contains("\"data\": [2,1,3,1,1,2]"));
+        assert!(tmj.contains("\"name\": \"start\", \"type\": \"start\", \"x\": 0, \"y\": 8,"));
+        assert!(tmj.contains("\"name\": \"exit\", \"type\": \"exit\", \"x\": 16, \"y\": 0,"));
+        assert!(tmj.contains("\"name\": \"item 1\", \"type\": \"item\", \"x\": 32, \"y\": 8,"));
+    }
+}